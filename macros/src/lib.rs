@@ -1,17 +1,129 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn;
 
-#[proc_macro_derive(ItemData)]
+/// Derives `shrub::ItemData` for a struct or enum.
+///
+/// Behaviour of the data can be configured with the `#[item_data(...)]` attribute:
+/// - `stack_eq` &mdash; items carrying this data stack when their data is equal according to `PartialEq`
+/// - `clone` &mdash; the data can be duplicated with `Clone`, see `ItemData::clone_data`
+/// - `debug` &mdash; the data is printed with `Debug`, see `ItemData::as_debug`
+/// - `inherit` &mdash; items may copy the data from their type to change it, see `InheritData`; requires `Clone`
+/// - `serde = "tag"` &mdash; the data is serialized under the tag, see `SerdeData`; requires the `serde` feature of shrub
+#[proc_macro_derive(ItemData, attributes(item_data))]
 pub fn item_data_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse_macro_input!(input as syn::DeriveInput);
 
+    let flags = match ItemDataFlags::parse(&ast.attrs) {
+        Ok(flags) => flags,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let name = &ast.ident;
     let generics = ast.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    let stack_eq = flags.stack_eq.then(|| {
+        quote! {
+            fn stack_eq(&self, other: &dyn shrub::ItemData) -> bool {
+                other.downcast_ref::<Self>().is_some_and(|other| self == other)
+            }
+        }
+    });
+
+    let clone = flags.clone.then(|| {
+        quote! {
+            fn clone_data(&self) -> ::std::option::Option<::std::boxed::Box<dyn shrub::ItemData>> {
+                ::std::option::Option::Some(::std::boxed::Box::new(::std::clone::Clone::clone(self)))
+            }
+        }
+    });
+
+    let debug = flags.debug.then(|| {
+        quote! {
+            fn as_debug(&self) -> ::std::option::Option<&dyn ::std::fmt::Debug> {
+                ::std::option::Option::Some(self)
+            }
+        }
+    });
+
+    let inherit = flags.inherit.then(|| {
+        quote! {
+            impl #impl_generics shrub::InheritData for #name #ty_generics #where_clause {}
+        }
+    });
+
+    let serde = flags.serde.map(|tag| {
+        quote! {
+            impl #impl_generics shrub::SerdeData for #name #ty_generics #where_clause {
+                const TAG: &'static str = #tag;
+            }
+        }
+    });
+
     let gen = quote! {
-        impl #impl_generics shrub::ItemData for #name #ty_generics #where_clause {}
+        impl #impl_generics shrub::ItemData for #name #ty_generics #where_clause {
+            #stack_eq
+            #clone
+            #debug
+        }
+        #inherit
+        #serde
     };
     gen.into()
 }
+
+#[derive(Default)]
+struct ItemDataFlags {
+    stack_eq: bool,
+    clone: bool,
+    debug: bool,
+    inherit: bool,
+    serde: Option<syn::LitStr>,
+}
+
+impl ItemDataFlags {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut flags = ItemDataFlags::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("item_data")) {
+            let list = match attr.parse_meta()? {
+                syn::Meta::List(list) => list,
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "expected `#[item_data(...)]`",
+                    ))
+                }
+            };
+            for nested in list.nested {
+                match nested {
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("stack_eq") => {
+                        flags.stack_eq = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("clone") => {
+                        flags.clone = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("debug") => {
+                        flags.debug = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("inherit") => {
+                        flags.inherit = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                        path,
+                        lit: syn::Lit::Str(tag),
+                        ..
+                    })) if path.is_ident("serde") => {
+                        flags.serde = Some(tag);
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "unknown item_data flag, expected `stack_eq`, `clone`, `debug`, `inherit` or `serde = \"tag\"`",
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(flags)
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    itemdata::impl_value_item_data, AddReceipt, Inventory, InventoryEntries, InventoryItems,
+    InventorySelector, Item, ShrubError, TransactionInventory,
+};
+
+/// The weight of a single item, so a stack weighs its count times as much.
+/// Usually added to an `ItemType`, but single items can override it with their own data.
+/// Items without a Weight weigh nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weight(pub f32);
+
+impl Weight {
+    /// Returns the weight of the whole stack.
+    pub fn of(item: &Item) -> f32 {
+        item.get_data::<Weight>().map_or(0.0, |weight| weight.0) * item.count() as f32
+    }
+}
+
+impl_value_item_data!(Weight);
+
+/// The volume of a single item, so a stack takes up its count times as much.
+/// Usually added to an `ItemType`, but single items can override it with their own data.
+/// Items without a Volume take up no space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Volume(pub f32);
+
+impl Volume {
+    /// Returns the volume of the whole stack.
+    pub fn of(item: &Item) -> f32 {
+        item.get_data::<Volume>().map_or(0.0, |volume| volume.0) * item.count() as f32
+    }
+}
+
+impl_value_item_data!(Volume);
+
+/// Wraps an inventory and limits the total `Weight` and `Volume` of its items.
+///
+/// Adding an item that would exceed a limit only adds as much of its stack as fits, see `Item::split`.
+/// The limits only guard adding items, so changing items in place or through `inner_mut` can exceed them.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{CapacityInventory, Inventory, ItemType, MaxStackSize, SlotInventory, Weight};
+///
+/// let stone = Arc::new(ItemType::with_data((Weight(2.5), MaxStackSize(64))));
+/// let mut backpack = CapacityInventory::new(SlotInventory::new(8)).with_max_weight(20.0);
+///
+/// let mut stones = stone.item_new();
+/// stones.set_count(10);
+/// let rest = backpack.add_item(stones).unwrap();
+/// assert_eq!(rest.count(), 2);
+/// assert_eq!(backpack.weight(), 20.0);
+/// assert_eq!(backpack.encumbrance(), Some(1.0));
+/// ```
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CapacityInventory<I> {
+    inventory: I,
+    max_weight: Option<f32>,
+    max_volume: Option<f32>,
+}
+
+impl<I> CapacityInventory<I> {
+    /// Wraps the inventory without any limits.
+    pub fn new(inventory: I) -> Self {
+        CapacityInventory {
+            inventory,
+            max_weight: None,
+            max_volume: None,
+        }
+    }
+
+    /// Limits the total weight of the items.
+    pub fn with_max_weight(mut self, max_weight: f32) -> Self {
+        self.max_weight = Some(max_weight);
+        self
+    }
+
+    /// Limits the total volume of the items.
+    pub fn with_max_volume(mut self, max_volume: f32) -> Self {
+        self.max_volume = Some(max_volume);
+        self
+    }
+
+    /// Returns the maximum total weight, or `None` when the weight isn't limited.
+    #[inline]
+    pub fn max_weight(&self) -> Option<f32> {
+        self.max_weight
+    }
+
+    /// Changes the maximum total weight. Items exceeding a lowered limit stay in the inventory.
+    #[inline]
+    pub fn set_max_weight(&mut self, max_weight: Option<f32>) {
+        self.max_weight = max_weight;
+    }
+
+    /// Returns the maximum total volume, or `None` when the volume isn't limited.
+    #[inline]
+    pub fn max_volume(&self) -> Option<f32> {
+        self.max_volume
+    }
+
+    /// Changes the maximum total volume. Items exceeding a lowered limit stay in the inventory.
+    #[inline]
+    pub fn set_max_volume(&mut self, max_volume: Option<f32>) {
+        self.max_volume = max_volume;
+    }
+
+    /// Borrows the wrapped inventory.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.inventory
+    }
+
+    /// Borrows the wrapped inventory as mutable. Items added through it aren't checked against the limits.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inventory
+    }
+
+    /// Unwraps the inventory.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.inventory
+    }
+}
+
+impl<I: InventoryItems> CapacityInventory<I> {
+    /// Returns the total weight of all items.
+    pub fn weight(&self) -> f32 {
+        self.inventory.items().map(Weight::of).sum()
+    }
+
+    /// Returns the total volume of all items.
+    pub fn volume(&self) -> f32 {
+        self.inventory.items().map(Volume::of).sum()
+    }
+
+    /// Returns how much weight can still be added, or `None` when the weight isn't limited.
+    pub fn remaining_weight(&self) -> Option<f32> {
+        Some((self.max_weight? - self.weight()).max(0.0))
+    }
+
+    /// Returns how much volume can still be added, or `None` when the volume isn't limited.
+    pub fn remaining_volume(&self) -> Option<f32> {
+        Some((self.max_volume? - self.volume()).max(0.0))
+    }
+
+    /// Returns the total weight relative to the maximum weight, which is above 1 when the inventory is overloaded.
+    /// Returns `None` when the weight isn't limited.
+    pub fn encumbrance(&self) -> Option<f32> {
+        Some(self.weight() / self.max_weight?)
+    }
+
+    /// Returns `true` when the whole stack stays within the limits.
+    /// The wrapped inventory might still reject it for other reasons.
+    pub fn fits(&self, item: &Item) -> bool {
+        self.fitting_count(item) >= item.count()
+    }
+
+    /// Returns how many items of the stack stay within the limits.
+    fn fitting_count(&self, item: &Item) -> u32 {
+        let limit = |remaining: Option<f32>, single: f32| match remaining {
+            Some(remaining) if single > 0.0 => (remaining / single).floor() as u32,
+            _ => u32::MAX,
+        };
+        let weight = item.get_data::<Weight>().map_or(0.0, |weight| weight.0);
+        let volume = item.get_data::<Volume>().map_or(0.0, |volume| volume.0);
+        limit(self.remaining_weight(), weight)
+            .min(limit(self.remaining_volume(), volume))
+            .min(item.count())
+    }
+}
+
+impl<I: Inventory + InventoryItems> Inventory for CapacityInventory<I> {
+    /// Adds as much of the stack as stays within the limits to the wrapped inventory and returns the rest.
+    /// When only a part fits but the stack can't be split, the whole item is returned.
+    fn add_item(&mut self, mut item: Item) -> Option<Item> {
+        let fitting = self.fitting_count(&item);
+        if fitting == item.count() {
+            return self.inventory.add_item(item);
+        }
+        let Some(part) = item.split(fitting) else {
+            return Some(item);
+        };
+        if let Some(rest) = self.inventory.add_item(part) {
+            item.set_count(item.count() + rest.count());
+        }
+        Some(item)
+    }
+}
+
+impl<S, I: InventorySelector<S>> InventorySelector<S> for CapacityInventory<I> {
+    fn get_item(&self, selector: S) -> Option<&Item> {
+        self.inventory.get_item(selector)
+    }
+
+    fn get_item_mut(&mut self, selector: S) -> Option<&mut Item> {
+        self.inventory.get_item_mut(selector)
+    }
+
+    fn remove_item(&mut self, selector: S) -> Option<Item> {
+        self.inventory.remove_item(selector)
+    }
+}
+
+impl<I: InventoryItems> InventoryItems for CapacityInventory<I> {
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.inventory.items()
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.inventory.items_mut()
+    }
+}
+
+impl<S, I: InventoryEntries<S>> InventoryEntries<S> for CapacityInventory<I> {
+    const STABLE_SELECTORS: bool = I::STABLE_SELECTORS;
+
+    fn entries(&self) -> impl Iterator<Item = (S, &Item)> {
+        self.inventory.entries()
+    }
+}
+
+impl<S, I: TransactionInventory<S> + InventoryItems> TransactionInventory<S>
+    for CapacityInventory<I>
+{
+    fn add_item_tracked(&mut self, item: Item) -> Result<AddReceipt<S>, ShrubError> {
+        if !self.fits(&item) {
+            return Err(ShrubError::InventoryFull(item));
+        }
+        self.inventory.add_item_tracked(item)
+    }
+
+    fn insert_item(&mut self, selector: S, item: Item) -> Option<Item> {
+        if !self.fits(&item) {
+            return Some(item);
+        }
+        self.inventory.insert_item(selector, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        CapacityInventory, Inventory, InventorySelector, ItemData, ItemType, MaxStackSize,
+        SlotInventory, TransactionInventory, Volume, Weight,
+    };
+
+    #[test]
+    fn reject_items_over_the_limits() {
+        let anvil = Arc::new(ItemType::with_data((Weight(50.0), Volume(1.0))));
+        let feather = Arc::new(ItemType::with_data((Volume(0.5), MaxStackSize(16))));
+        let mut inventory = CapacityInventory::new(SlotInventory::new(4))
+            .with_max_weight(80.0)
+            .with_max_volume(4.0);
+
+        assert!(inventory.add_item(anvil.item_new()).is_none());
+        assert!(inventory.add_item(anvil.item_new()).is_some());
+        assert_eq!(inventory.weight(), 50.0);
+        assert_eq!(inventory.remaining_weight(), Some(30.0));
+
+        let mut feathers = feather.item_new();
+        feathers.set_count(10);
+        assert_eq!(inventory.add_item(feathers).unwrap().count(), 4);
+        assert_eq!(inventory.get_item(1).unwrap().count(), 6);
+        assert_eq!(inventory.volume(), 4.0);
+        assert_eq!(inventory.remaining_volume(), Some(0.0));
+        assert!(!inventory.fits(&feather.item_new()));
+    }
+
+    #[test]
+    fn items_override_the_weight_of_their_type() {
+        let bag = Arc::new(ItemType::with_data(Weight(1.0)));
+        let mut inventory = CapacityInventory::new(SlotInventory::new(4)).with_max_weight(10.0);
+
+        assert!(inventory
+            .add_item(bag.item_with_data(Weight(12.0)))
+            .is_some());
+        assert!(inventory.add_item(bag.item_new()).is_none());
+        assert_eq!(inventory.encumbrance(), Some(0.1));
+
+        inventory.set_max_weight(None);
+        assert!(inventory
+            .add_item(bag.item_with_data(Weight(12.0)))
+            .is_none());
+        assert_eq!(inventory.weight(), 13.0);
+        assert_eq!(inventory.encumbrance(), None);
+    }
+
+    #[test]
+    fn keep_stacks_with_data_whole() {
+        struct Minted;
+        impl ItemData for Minted {}
+
+        let coin = Arc::new(ItemType::with_data((Weight(1.0), MaxStackSize(100))));
+        let mut inventory = CapacityInventory::new(SlotInventory::new(4)).with_max_weight(5.0);
+        let mut coins = coin.item_with_data(Minted);
+        coins.set_count(8);
+
+        // Minted can't be duplicated, so the stack can't be split
+        assert_eq!(inventory.add_item(coins).unwrap().count(), 8);
+        assert!(inventory.inner().is_empty());
+
+        let mut coins = coin.item_with_data(MaxStackSize(100));
+        coins.set_count(8);
+        assert_eq!(inventory.add_item(coins).unwrap().count(), 3);
+        assert_eq!(inventory.weight(), 5.0);
+
+        let mut coins = coin.item_new();
+        coins.set_count(8);
+        assert!(inventory.add_item_tracked(coins).is_err());
+    }
+}
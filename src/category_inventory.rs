@@ -0,0 +1,343 @@
+use std::fmt;
+
+use crate::{
+    AddReceipt, Inventory, InventoryEntries, InventoryItems, InventorySelector, Item, ItemData,
+    ShrubError, TransactionInventory,
+};
+
+type RouteFn = Box<dyn Fn(&Item) -> bool + Send + Sync>;
+
+enum Route {
+    Match(RouteFn),
+    Fallback,
+    Overflow,
+}
+
+struct Child<I> {
+    route: Route,
+    inventory: I,
+}
+
+/// Routes items into child inventories, for example to keep weapons, food and blocks apart.
+///
+/// An added item goes to the children whose route matches it, in the order they were added.
+/// Items that match no route go to the fallback children instead.
+/// Whatever doesn't fit there, because all of them are full, goes to the overflow children.
+///
+/// Items are selected with the index of the child, in the order the children were added, and the selector within the child.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{CategoryInventory, Inventory, InventorySelector, ItemData, ItemType, SlotInventory};
+///
+/// struct Weapon;
+/// impl ItemData for Weapon {}
+///
+/// let sword = Arc::new(ItemType::with_data(Weapon));
+/// let stone = Arc::new(ItemType::new());
+/// let mut inventory = CategoryInventory::new()
+///     .with_data_route::<Weapon>(SlotInventory::new(1))
+///     .with_fallback(SlotInventory::new(4))
+///     .with_overflow(SlotInventory::new(1));
+///
+/// inventory.add_item(stone.item_new());
+/// inventory.add_item(sword.item_new());
+/// inventory.add_item(sword.item_new());
+///
+/// assert!(inventory.get_item((0, 0)).is_some()); // the first sword
+/// assert!(inventory.get_item((1, 0)).is_some()); // the stone
+/// assert!(inventory.get_item((2, 0)).is_some()); // the second sword
+/// ```
+pub struct CategoryInventory<I> {
+    children: Vec<Child<I>>,
+}
+
+impl<I> CategoryInventory<I> {
+    /// Creates an inventory without children, which doesn't accept any items.
+    pub fn new() -> Self {
+        CategoryInventory {
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds a child that takes the items for which the predicate returns `true`.
+    pub fn with_route<F>(mut self, predicate: F, inventory: I) -> Self
+    where
+        F: Fn(&Item) -> bool + Send + Sync + 'static,
+    {
+        self.push(Route::Match(Box::new(predicate)), inventory);
+        self
+    }
+
+    /// Adds a child that takes the items carrying data of type `D`, on the item or its type.
+    pub fn with_data_route<D: ItemData>(self, inventory: I) -> Self {
+        self.with_route(|item| item.get_data::<D>().is_some(), inventory)
+    }
+
+    /// Adds a child that takes the items which match no route.
+    pub fn with_fallback(mut self, inventory: I) -> Self {
+        self.push(Route::Fallback, inventory);
+        self
+    }
+
+    /// Adds a child that takes what doesn't fit into the routed or fallback children.
+    pub fn with_overflow(mut self, inventory: I) -> Self {
+        self.push(Route::Overflow, inventory);
+        self
+    }
+
+    fn push(&mut self, route: Route, inventory: I) {
+        self.children.push(Child { route, inventory });
+    }
+
+    /// Returns the number of children.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns `true` when the inventory has no children.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Borrows the child at the index.
+    pub fn child(&self, index: usize) -> Option<&I> {
+        self.children.get(index).map(|child| &child.inventory)
+    }
+
+    /// Borrows the child at the index as mutable. Items added through it aren't routed.
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut I> {
+        self.children
+            .get_mut(index)
+            .map(|child| &mut child.inventory)
+    }
+
+    /// Iterates over the children in the order they were added.
+    pub fn children(&self) -> impl Iterator<Item = &I> {
+        self.children.iter().map(|child| &child.inventory)
+    }
+
+    /// Returns the indices of the children the item is offered to, in order.
+    fn route(&self, item: &Item) -> Vec<usize> {
+        let indices = |filter: &dyn Fn(&Route) -> bool| {
+            self.children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| filter(&child.route))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        let mut route = indices(&|route| matches!(route, Route::Match(f) if f(item)));
+        if route.is_empty() {
+            route = indices(&|route| matches!(route, Route::Fallback));
+        }
+        route.extend(indices(&|route| matches!(route, Route::Overflow)));
+        route
+    }
+}
+
+impl<I> Default for CategoryInventory<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: fmt::Debug> fmt::Debug for CategoryInventory<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.children()).finish()
+    }
+}
+
+impl<I: Inventory> Inventory for CategoryInventory<I> {
+    fn add_item(&mut self, item: Item) -> Option<Item> {
+        let mut rest = item;
+        for index in self.route(&rest) {
+            rest = self.children[index].inventory.add_item(rest)?;
+        }
+        Some(rest)
+    }
+}
+
+impl<S, I: InventorySelector<S>> InventorySelector<(usize, S)> for CategoryInventory<I> {
+    fn get_item(&self, (index, selector): (usize, S)) -> Option<&Item> {
+        self.child(index)?.get_item(selector)
+    }
+
+    fn get_item_mut(&mut self, (index, selector): (usize, S)) -> Option<&mut Item> {
+        self.child_mut(index)?.get_item_mut(selector)
+    }
+
+    fn remove_item(&mut self, (index, selector): (usize, S)) -> Option<Item> {
+        self.child_mut(index)?.remove_item(selector)
+    }
+}
+
+impl<I: InventoryItems> InventoryItems for CategoryInventory<I> {
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.children
+            .iter()
+            .flat_map(|child| child.inventory.items())
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.children
+            .iter_mut()
+            .flat_map(|child| child.inventory.items_mut())
+    }
+}
+
+impl<S, I: InventoryEntries<S>> InventoryEntries<(usize, S)> for CategoryInventory<I> {
+    const STABLE_SELECTORS: bool = I::STABLE_SELECTORS;
+
+    fn entries(&self) -> impl Iterator<Item = ((usize, S), &Item)> {
+        self.children.iter().enumerate().flat_map(|(index, child)| {
+            child
+                .inventory
+                .entries()
+                .map(move |(selector, item)| ((index, selector), item))
+        })
+    }
+}
+
+impl<S, I: TransactionInventory<S>> TransactionInventory<(usize, S)> for CategoryInventory<I> {
+    /// Adds the whole item to the first child on its route that can take it whole.
+    fn add_item_tracked(&mut self, item: Item) -> Result<AddReceipt<(usize, S)>, ShrubError> {
+        let mut item = item;
+        for index in self.route(&item) {
+            match self.children[index].inventory.add_item_tracked(item) {
+                Ok(receipt) => return Ok(receipt.map(|selector| (index, selector))),
+                Err(error) => item = error.into_item().unwrap(),
+            }
+        }
+        Err(ShrubError::InventoryFull(item))
+    }
+
+    fn insert_item(&mut self, (index, selector): (usize, S), item: Item) -> Option<Item> {
+        match self.child_mut(index) {
+            Some(child) => child.insert_item(selector, item),
+            None => Some(item),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        CategoryInventory, Inventory, InventoryItems, InventorySelector, Item, ItemData, ItemType,
+        MaxStackSize, SlotInventory, Transaction,
+    };
+
+    struct Food;
+    impl ItemData for Food {}
+
+    #[test]
+    fn route_by_predicate() {
+        let apple = Arc::new(ItemType::with_data((Food, MaxStackSize(8))));
+        let stone = Arc::new(ItemType::new());
+        let mut inventory = CategoryInventory::new()
+            .with_route(|item| item.count() > 4, SlotInventory::new(1))
+            .with_data_route::<Food>(SlotInventory::new(1));
+
+        let mut apples = apple.item_new();
+        apples.set_count(6);
+        assert!(inventory.add_item(apples).is_none());
+        assert!(inventory.add_item(apple.item_new()).is_none());
+        assert_eq!(inventory.get_item((0, 0)).unwrap().count(), 6);
+        assert_eq!(inventory.get_item((1, 0)).unwrap().count(), 1);
+
+        // nothing takes the stone without a fallback
+        assert!(inventory.add_item(stone.item_new()).is_some());
+        assert_eq!(inventory.items().count(), 2);
+    }
+
+    #[test]
+    fn route_by_data_value() {
+        #[derive(PartialEq)]
+        enum Category {
+            Weapon,
+            Food,
+            Block,
+        }
+        struct CategoryData(Category);
+        impl ItemData for CategoryData {}
+
+        let category = |category: Category| {
+            move |item: &Item| {
+                item.get_data::<CategoryData>()
+                    .is_some_and(|data| data.0 == category)
+            }
+        };
+        let mut inventory = CategoryInventory::new()
+            .with_route(category(Category::Weapon), SlotInventory::new(1))
+            .with_route(category(Category::Food), SlotInventory::new(1))
+            .with_route(category(Category::Block), SlotInventory::new(1));
+        assert_eq!(inventory.len(), 3);
+        assert!(inventory.children().all(|child| child.items().count() == 0));
+
+        let apple = Arc::new(ItemType::with_data(CategoryData(Category::Food)));
+        let stone = Arc::new(ItemType::with_data(CategoryData(Category::Block)));
+        let sword = Arc::new(ItemType::with_data(CategoryData(Category::Weapon)));
+        for item_type in [&apple, &stone, &sword] {
+            assert!(inventory.add_item(item_type.item_new()).is_none());
+        }
+
+        assert_eq!(inventory.get_item((0, 0)).unwrap().item_type(), &sword);
+        assert_eq!(inventory.get_item((1, 0)).unwrap().item_type(), &apple);
+        assert_eq!(inventory.child(2).unwrap().items().count(), 1);
+        assert!(inventory.remove_item((2, 0)).is_some());
+        assert!(inventory.add_item(sword.item_new()).is_some());
+    }
+
+    #[test]
+    fn fall_back_and_overflow() {
+        let apple = Arc::new(ItemType::with_data((Food, MaxStackSize(4))));
+        let stone = Arc::new(ItemType::new());
+        let mut inventory = CategoryInventory::new()
+            .with_data_route::<Food>(SlotInventory::new(1))
+            .with_fallback(SlotInventory::new(1))
+            .with_overflow(SlotInventory::new(2));
+
+        let mut apples = apple.item_new();
+        apples.set_count(3);
+        assert!(inventory.add_item(apples).is_none());
+        let mut apples = apple.item_new();
+        apples.set_count(3);
+        assert!(inventory.add_item(apples).is_none());
+        assert_eq!(inventory.get_item((0, 0)).unwrap().count(), 4);
+        assert_eq!(inventory.get_item((2, 0)).unwrap().count(), 2);
+        // apples never go to the fallback
+        assert!(inventory.child(1).unwrap().is_empty());
+
+        assert!(inventory.add_item(stone.item_new()).is_none());
+        assert!(inventory.get_item((1, 0)).is_some());
+        assert!(inventory.add_item(stone.item_new()).is_none());
+        assert!(inventory.get_item((2, 1)).is_some());
+        assert!(inventory.add_item(stone.item_new()).is_some());
+    }
+
+    #[test]
+    fn roll_back_routed_items() {
+        let apple = Arc::new(ItemType::with_data(Food));
+        let stone = Arc::new(ItemType::new());
+        let mut inventory = CategoryInventory::new()
+            .with_data_route::<Food>(SlotInventory::new(1))
+            .with_fallback(SlotInventory::new(1));
+
+        let mut transaction = Transaction::new();
+        let handle = transaction.inventory(&mut inventory);
+        transaction
+            .add_item(handle, apple.item_new())
+            .add_item(handle, stone.item_new())
+            .add_item(handle, stone.item_new());
+        let error = transaction.commit().unwrap_err();
+
+        assert_eq!(error.operation(), 2);
+        assert_eq!(error.items().len(), 3);
+        assert_eq!(inventory.items().count(), 0);
+    }
+}
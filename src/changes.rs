@@ -0,0 +1,79 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Hands out generations. It is shared by all items and inventories, so their generations can be compared.
+static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// A point in time at which an `Item`, one of its data entries or an `Observed` inventory changed, see `Item::track_changes`.
+///
+/// Generations come from a clock shared by the whole program, so a later change always has a greater generation.
+/// Remember `Generation::current` when rendering or sending something and compare against it later to find what changed since.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{Generation, ItemType, MaxStackSize};
+///
+/// let mut arrows = Arc::new(ItemType::with_data(MaxStackSize(64))).item_new();
+/// arrows.track_changes();
+///
+/// let synced = Generation::current();
+/// assert!(!arrows.changed_since(synced));
+/// arrows.set_count(12);
+/// assert!(arrows.changed_since(synced));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Generation(u64);
+
+impl Generation {
+    /// The generation before any change was made.
+    pub const ZERO: Generation = Generation(0);
+
+    /// Returns the generation of the latest change made anywhere.
+    #[inline]
+    pub fn current() -> Self {
+        Generation(CLOCK.load(Ordering::Relaxed))
+    }
+
+    /// Returns the generation as a number, for example to send it along with the changes.
+    #[inline]
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Hands out the generation of a new change.
+    pub(crate) fn next() -> Self {
+        Generation(CLOCK.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+/// The generations of an item that tracks its changes.
+#[derive(Clone)]
+pub(crate) struct ChangeTracker {
+    pub(crate) item: Generation,
+    /// The generation of every data entry, which is kept when the entry is removed so the removal counts as a change.
+    pub(crate) data: HashMap<TypeId, Generation>,
+}
+
+impl ChangeTracker {
+    /// Starts tracking an item which carries data of the given types.
+    pub(crate) fn new(data: impl Iterator<Item = TypeId>) -> Self {
+        let generation = Generation::next();
+        ChangeTracker {
+            item: generation,
+            data: data.map(|type_id| (type_id, generation)).collect(),
+        }
+    }
+
+    pub(crate) fn item_changed(&mut self) {
+        self.item = Generation::next();
+    }
+
+    pub(crate) fn data_changed(&mut self, type_id: TypeId) {
+        self.item = Generation::next();
+        self.data.insert(type_id, self.item);
+    }
+}
@@ -0,0 +1,1210 @@
+use std::{
+    any::TypeId,
+    cell::Cell,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    item::ItemDataMap, registry::is_valid_key, serialization::DataSeed, ItemData, ItemRegistry,
+    ItemType, ItemTypeId,
+};
+
+/// What went wrong while loading item definitions, see `DefinitionError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefinitionErrorKind {
+    /// The file couldn't be read.
+    Io,
+    /// The extension of the file doesn't belong to a format enabled by the crate features.
+    UnsupportedFormat,
+    /// The file isn't valid in its format, or a definition or data value doesn't have the expected shape.
+    Malformed,
+    /// A data block uses a tag that isn't registered, see `ItemRegistry::register_data`.
+    UnknownDataTag,
+    /// A parent is neither registered nor defined further up in the same file.
+    UnknownParent,
+    /// A reloaded item type would derive from itself through its parents.
+    ParentCycle,
+    /// An item type key is malformed, see `ItemRegistry::register`.
+    InvalidKey,
+    /// An item type key is already registered or defined twice.
+    DuplicateKey,
+}
+
+/// An error of `ItemRegistry::load_definitions_file` and friends, pointing at the place in the file that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionError {
+    kind: DefinitionErrorKind,
+    message: String,
+    file: Option<PathBuf>,
+    position: Option<(usize, usize)>,
+}
+
+#[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+impl DefinitionError {
+    fn new(kind: DefinitionErrorKind, message: impl Into<String>) -> Self {
+        DefinitionError {
+            kind,
+            message: message.into(),
+            file: None,
+            position: None,
+        }
+    }
+
+    fn at(mut self, line: usize, column: usize) -> Self {
+        if line != 0 {
+            self.position = Some((line, column));
+        }
+        self
+    }
+}
+
+impl DefinitionError {
+    /// Returns the kind of the error.
+    #[inline]
+    pub fn kind(&self) -> DefinitionErrorKind {
+        self.kind
+    }
+
+    /// Returns the description of the error, without its location.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the file the definitions were loaded from, or `None` when they were loaded from a string.
+    #[inline]
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the line of the error, starting at 1, or `None` when it doesn't belong to a place in the file.
+    #[inline]
+    pub fn line(&self) -> Option<usize> {
+        Some(self.position?.0)
+    }
+
+    /// Returns the column of the error, starting at 1, or `None` when it doesn't belong to a place in the file.
+    #[inline]
+    pub fn column(&self) -> Option<usize> {
+        Some(self.position?.1)
+    }
+}
+
+impl fmt::Display for DefinitionError {
+    /// Prints the file, line and column in front of the message, like `items/weapons.json:3:14: <message>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some((line, column)) = self.position {
+            write!(f, "{line}:{column}:")?;
+        }
+        if self.file.is_some() || self.position.is_some() {
+            f.write_str(" ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+/// Lists how reloading definitions changed the `ItemRegistry`, see `ItemRegistry::reload_definitions`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReloadReport {
+    added: Vec<ItemTypeId>,
+    changed: Vec<ItemTypeChange>,
+}
+
+impl ReloadReport {
+    /// Returns `true` when reloading didn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty()
+    }
+
+    /// Iterates over the ids of the item types that weren't registered before, in the order they were defined.
+    pub fn added(&self) -> impl Iterator<Item = ItemTypeId> + '_ {
+        self.added.iter().copied()
+    }
+
+    /// Iterates over the item types whose definition changed, in the order they were defined.
+    /// Item types which were defined exactly like before aren't included.
+    pub fn changed(&self) -> impl Iterator<Item = &ItemTypeChange> + '_ {
+        self.changed.iter()
+    }
+
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+    fn extend(&mut self, other: ReloadReport) {
+        self.added.extend(other.added);
+        self.changed.extend(other.changed);
+    }
+}
+
+/// How the definition of a reloaded item type changed, see `ReloadReport::changed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemTypeChange {
+    id: ItemTypeId,
+    data: Vec<Box<str>>,
+    tags_changed: bool,
+    parent_changed: bool,
+}
+
+impl ItemTypeChange {
+    /// Returns the id of the changed item type.
+    #[inline]
+    pub fn id(&self) -> ItemTypeId {
+        self.id
+    }
+
+    /// Iterates over the tags of the data entries which were added, removed or changed, sorted by tag.
+    /// Data without a registered tag is listed by its type name, see `ItemData::type_name`.
+    pub fn data(&self) -> impl Iterator<Item = &str> + '_ {
+        self.data.iter().map(|tag| &**tag)
+    }
+
+    /// Returns `true` when the item type got or lost tags, not counting the tags of its parents.
+    #[inline]
+    pub fn tags_changed(&self) -> bool {
+        self.tags_changed
+    }
+
+    /// Returns `true` when the item type derives from another parent than before.
+    #[inline]
+    pub fn parent_changed(&self) -> bool {
+        self.parent_changed
+    }
+}
+
+/// A parsed definition which is ready to be registered.
+struct Definition {
+    key: String,
+    parent: Option<String>,
+    item_type: ItemType,
+}
+
+/// Deserializes a map from item type keys to definitions.
+struct DefinitionsSeed<'r> {
+    registry: &'r ItemRegistry,
+    /// Whether registered item types may be defined again, which replaces them.
+    redefine: bool,
+    /// The kind of the error the seed failed with, as formats only keep its message.
+    error_kind: &'r Cell<DefinitionErrorKind>,
+}
+
+impl DefinitionsSeed<'_> {
+    fn error<E: de::Error>(&self, kind: DefinitionErrorKind, message: fmt::Arguments) -> E {
+        self.error_kind.set(kind);
+        E::custom(message)
+    }
+
+    /// Returns `true` when `parent` is `key` or derives from it,
+    /// following the parents defined further up in the file before the registered ones.
+    fn derives_from(
+        &self,
+        defined: &HashMap<String, Option<String>>,
+        parent: &str,
+        key: &str,
+    ) -> bool {
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == key {
+                return true;
+            }
+            ancestor = match defined.get(current) {
+                Some(parent) => parent.as_deref(),
+                None => self
+                    .registry
+                    .get_by_key(current)
+                    .and_then(|item_type| item_type.parent()?.id())
+                    .and_then(|parent| self.registry.key_of(parent)),
+            };
+        }
+        false
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DefinitionsSeed<'_> {
+    type Value = Vec<Definition>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DefinitionsSeed<'_> {
+    type Value = Vec<Definition>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of item type keys to item definitions")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut definitions = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        // the parents of the definitions further up in the file
+        let mut defined = HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if !is_valid_key(&key) {
+                return Err(self.error(
+                    DefinitionErrorKind::InvalidKey,
+                    format_args!("malformed item type key `{key}`"),
+                ));
+            }
+            if defined.contains_key(&key) || !self.redefine && self.registry.contains_key(&key) {
+                return Err(self.error(
+                    DefinitionErrorKind::DuplicateKey,
+                    format_args!("item type `{key}` is already defined"),
+                ));
+            }
+            let (parent, item_type) = map.next_value_seed(DefinitionSeed {
+                definitions: &self,
+                defined: &defined,
+                key: &key,
+            })?;
+            defined.insert(key.clone(), parent.clone());
+            definitions.push(Definition {
+                key,
+                parent,
+                item_type,
+            });
+        }
+        Ok(definitions)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum DefinitionField {
+    Parent,
+    Tags,
+    Data,
+}
+
+/// Deserializes a single definition into its parent key and the unregistered ItemType.
+struct DefinitionSeed<'a, 'r> {
+    definitions: &'a DefinitionsSeed<'r>,
+    defined: &'a HashMap<String, Option<String>>,
+    key: &'a str,
+}
+
+impl<'de> DeserializeSeed<'de> for DefinitionSeed<'_, '_> {
+    type Value = (Option<String>, ItemType);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("ItemDefinition", &["parent", "tags", "data"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for DefinitionSeed<'_, '_> {
+    type Value = (Option<String>, ItemType);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an item definition")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut parent = None;
+        let mut item_type = ItemType::new();
+        while let Some(field) = map.next_key()? {
+            match field {
+                DefinitionField::Parent => {
+                    let key: String = map.next_value()?;
+                    if !self.definitions.registry.contains_key(&key)
+                        && !self.defined.contains_key(&key)
+                    {
+                        return Err(self.definitions.error(
+                            DefinitionErrorKind::UnknownParent,
+                            format_args!("unknown parent item type `{key}`"),
+                        ));
+                    }
+                    if self.definitions.derives_from(self.defined, &key, self.key) {
+                        return Err(self.definitions.error(
+                            DefinitionErrorKind::ParentCycle,
+                            format_args!(
+                                "item type `{}` can't derive from itself through `{key}`",
+                                self.key
+                            ),
+                        ));
+                    }
+                    parent = Some(key);
+                }
+                DefinitionField::Tags => {
+                    for tag in map.next_value::<Vec<String>>()? {
+                        item_type.add_tag(&tag);
+                    }
+                }
+                DefinitionField::Data => {
+                    *item_type.data_mut() = map.next_value_seed(DataBlockSeed(self.definitions))?;
+                }
+            }
+        }
+        Ok((parent, item_type))
+    }
+}
+
+/// Deserializes a map from data tags to data, rejecting tags which aren't registered.
+struct DataBlockSeed<'a, 'r>(&'a DefinitionsSeed<'r>);
+
+impl<'de> DeserializeSeed<'de> for DataBlockSeed<'_, '_> {
+    type Value = ItemDataMap;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DataBlockSeed<'_, '_> {
+    type Value = ItemDataMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of item data tags to item data")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut data = ItemDataMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(tag) = map.next_key::<String>()? {
+            let Some((type_id, registration)) = self.0.registry.data_tags.get_by_tag(&tag) else {
+                return Err(self.0.error(
+                    DefinitionErrorKind::UnknownDataTag,
+                    format_args!("unknown item data tag `{tag}`"),
+                ));
+            };
+            data.insert(type_id, map.next_value_seed(DataSeed(registration))?);
+        }
+        Ok(data)
+    }
+}
+
+impl ItemRegistry {
+    /// Registers the item types defined by the deserializer and returns their ids in the order they were defined.
+    ///
+    /// Definitions are a map from item type keys to an optional `parent` key, a list of `tags`
+    /// and a `data` block which maps registered data tags to their values, see `ItemRegistry::register_data`.
+    /// A parent has to be registered already or defined further up in the same map.
+    /// Nothing is registered when any of the definitions is invalid.
+    ///
+    /// This works with any self-describing format. `load_definitions_json`, `load_definitions_ron`,
+    /// `load_definitions_toml` and `load_definitions_file` additionally report where in the file an error occurred.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, MaxStackSize, Weight};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let definitions = r#"{
+    ///     "core:weapon": { "tags": ["core:equipment"], "data": { "shrub:max_stack_size": 1 } },
+    ///     "core:sword": { "parent": "core:weapon", "data": { "shrub:weight": 3.5 } }
+    /// }"#;
+    /// let ids = registry
+    ///     .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+    ///     .unwrap();
+    ///
+    /// let sword = &registry[ids[1]];
+    /// assert_eq!(sword.key(), Some("core:sword"));
+    /// assert!(sword.has_tag("core:equipment"));
+    /// assert_eq!(sword.get_data::<MaxStackSize>().as_deref(), Some(&MaxStackSize(1)));
+    /// assert_eq!(sword.get_data::<Weight>().as_deref(), Some(&Weight(3.5)));
+    /// ```
+    pub fn load_definitions<'de, D: Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<Vec<ItemTypeId>, D::Error> {
+        let definitions = self
+            .parse_definitions(false, |seed| seed.deserialize(deserializer))
+            .map_err(|(error, _)| error)?;
+        Ok(self.register_definitions(definitions))
+    }
+
+    /// Like `load_definitions`, but item types which are registered already are replaced by their new definition,
+    /// see `ItemRegistry::replace`. Existing `Item`s of these types keep working and use the new definition from now on.
+    ///
+    /// Returns a report of the added item types and the changed definitions.
+    /// Item types that are registered but missing from the definitions stay unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, Weight};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let definitions = r#"{ "core:sword": { "tags": ["core:weapons"], "data": { "shrub:weight": 3.5 } } }"#;
+    /// let ids = registry
+    ///     .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+    ///     .unwrap();
+    /// let sword = registry[ids[0]].item_new();
+    ///
+    /// let definitions = r#"{ "core:sword": { "tags": ["core:weapons"], "data": { "shrub:weight": 2.0 } } }"#;
+    /// let report = registry
+    ///     .reload_definitions(&mut serde_json::Deserializer::from_str(definitions))
+    ///     .unwrap();
+    /// let change = report.changed().next().unwrap();
+    /// assert_eq!(change.id(), ids[0]);
+    /// assert_eq!(change.data().collect::<Vec<_>>(), vec!["shrub:weight"]);
+    /// assert_eq!(sword.get_data::<Weight>().as_deref(), Some(&Weight(2.0)));
+    /// ```
+    pub fn reload_definitions<'de, D: Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<ReloadReport, D::Error> {
+        let definitions = self
+            .parse_definitions(true, |seed| seed.deserialize(deserializer))
+            .map_err(|(error, _)| error)?;
+        Ok(self.redefine(definitions))
+    }
+
+    /// Parses the definitions with `parse` inside of a `serde_scope`.
+    /// When parsing fails, the error is returned together with its kind.
+    fn parse_definitions<E>(
+        &self,
+        redefine: bool,
+        parse: impl FnOnce(DefinitionsSeed) -> Result<Vec<Definition>, E>,
+    ) -> Result<Vec<Definition>, (E, DefinitionErrorKind)> {
+        let error_kind = Cell::new(DefinitionErrorKind::Malformed);
+        let seed = DefinitionsSeed {
+            registry: self,
+            redefine,
+            error_kind: &error_kind,
+        };
+        self.serde_scope(|| parse(seed))
+            .map_err(|error| (error, error_kind.get()))
+    }
+
+    fn register_definitions(&mut self, definitions: Vec<Definition>) -> Vec<ItemTypeId> {
+        definitions
+            .into_iter()
+            .map(|definition| {
+                let (key, item_type) = self.resolve_parent(definition);
+                self.register(&key, item_type)
+            })
+            .collect()
+    }
+
+    /// Registers new definitions and replaces the registered item types of the others.
+    fn redefine(&mut self, definitions: Vec<Definition>) -> ReloadReport {
+        let mut report = ReloadReport::default();
+        for definition in definitions {
+            let (key, item_type) = self.resolve_parent(definition);
+            let Some(id) = self.id_of(&key) else {
+                report.added.push(self.register(&key, item_type));
+                continue;
+            };
+            let item_type = self.prepare_replacement(id, item_type);
+            if let Some(change) = self.item_type_change(&self[id], &item_type) {
+                report.changed.push(change);
+            }
+            self.redefine_in_place(id, item_type);
+        }
+        report
+    }
+
+    /// Gives the ItemType of the definition its registered parent.
+    fn resolve_parent(&self, definition: Definition) -> (String, ItemType) {
+        let Definition {
+            key,
+            parent,
+            mut item_type,
+        } = definition;
+        if let Some(parent) = parent {
+            item_type.set_parent(self.get_by_key(&parent).cloned());
+        }
+        (key, item_type)
+    }
+
+    /// Compares the definitions of two versions of an ItemType, returning `None` when they are the same.
+    /// Data is compared by its serialized form.
+    fn item_type_change(&self, old: &ItemType, new: &ItemType) -> Option<ItemTypeChange> {
+        let (old_definition, new_definition) = (old.definition(), new.definition());
+        let (old_data, new_data) = (&old_definition.data, &new_definition.data);
+        let type_ids: BTreeSet<&TypeId> = old_data.keys().chain(new_data.keys()).collect();
+        let mut data: Vec<Box<str>> = self.serde_scope(|| {
+            type_ids
+                .into_iter()
+                .filter_map(|type_id| {
+                    let (old, new) = (old_data.get(type_id), new_data.get(type_id));
+                    let value = |data: Option<&Box<dyn ItemData>>| {
+                        self.data_tags.to_value(type_id, data?.as_ref())
+                    };
+                    let unchanged = old.is_some() == new.is_some()
+                        && value(old).is_some_and(|old| Some(old) == value(new));
+                    if unchanged {
+                        return None;
+                    }
+                    let data = old.or(new)?;
+                    let tag = self.data_tags.tag_of(type_id).unwrap_or(data.type_name());
+                    Some(tag.into())
+                })
+                .collect()
+        });
+        data.sort_unstable();
+        let change = ItemTypeChange {
+            id: new.id()?,
+            data,
+            tags_changed: old_definition.tags != new_definition.tags,
+            parent_changed: old_definition
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.key())
+                != new_definition
+                    .parent
+                    .as_ref()
+                    .and_then(|parent| parent.key()),
+        };
+        let changed = !change.data.is_empty() || change.tags_changed || change.parent_changed;
+        changed.then_some(change)
+    }
+
+    /// Like `load_definitions`, but reads the definitions from JSON and reports the line and column of errors.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{DefinitionErrorKind, ItemRegistry};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let error = registry
+    ///     .load_definitions_json("{\n  \"core:wand\": { \"data\": { \"magic:mana\": 30 } }\n}")
+    ///     .unwrap_err();
+    /// assert_eq!(error.kind(), DefinitionErrorKind::UnknownDataTag);
+    /// assert_eq!(error.line(), Some(2));
+    /// assert!(registry.is_empty());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn load_definitions_json(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<ItemTypeId>, DefinitionError> {
+        let definitions = self.parse_definitions_json(source, false)?;
+        Ok(self.register_definitions(definitions))
+    }
+
+    #[cfg(feature = "json")]
+    fn parse_definitions_json(
+        &self,
+        source: &str,
+        redefine: bool,
+    ) -> Result<Vec<Definition>, DefinitionError> {
+        self.parse_definitions(redefine, |seed| {
+            let mut deserializer = serde_json::Deserializer::from_str(source);
+            let definitions = seed.deserialize(&mut deserializer)?;
+            deserializer.end()?;
+            Ok(definitions)
+        })
+        .map_err(|(error, kind): (serde_json::Error, _)| {
+            // the message of a json error ends with its location
+            let message = error.to_string();
+            let location = format!(" at line {} column {}", error.line(), error.column());
+            let message = message.strip_suffix(&location).unwrap_or(&message);
+            DefinitionError::new(kind, message).at(error.line(), error.column())
+        })
+    }
+
+    /// Like `load_definitions`, but reads the definitions from RON and reports the line and column of errors.
+    /// Data which wraps a single value, like `Weight`, is written as the plain value.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, Weight};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let ids = registry
+    ///     .load_definitions_ron(r#"{ "core:stone": (tags: ["core:rocks"], data: { "shrub:weight": 2.0 }) }"#)
+    ///     .unwrap();
+    /// assert_eq!(registry[ids[0]].get_data::<Weight>().as_deref(), Some(&Weight(2.0)));
+    /// ```
+    #[cfg(feature = "ron")]
+    pub fn load_definitions_ron(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<ItemTypeId>, DefinitionError> {
+        let definitions = self.parse_definitions_ron(source, false)?;
+        Ok(self.register_definitions(definitions))
+    }
+
+    #[cfg(feature = "ron")]
+    fn parse_definitions_ron(
+        &self,
+        source: &str,
+        redefine: bool,
+    ) -> Result<Vec<Definition>, DefinitionError> {
+        let options = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::UNWRAP_NEWTYPES);
+        self.parse_definitions(redefine, |seed| options.from_str_seed(source, seed))
+            .map_err(|(error, kind)| {
+                DefinitionError::new(kind, error.code.to_string())
+                    .at(error.position.line, error.position.col)
+            })
+    }
+
+    /// Like `load_definitions`, but reads the definitions from TOML and reports the line and column of errors.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, MaxStackSize};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let ids = registry
+    ///     .load_definitions_toml(
+    ///         r#"
+    ///         ["core:arrow"]
+    ///         tags = ["core:ammo"]
+    ///         data = { "shrub:max_stack_size" = 64 }
+    ///         "#,
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(registry[ids[0]].max_stack_size(), 64);
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn load_definitions_toml(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<ItemTypeId>, DefinitionError> {
+        let definitions = self.parse_definitions_toml(source, false)?;
+        Ok(self.register_definitions(definitions))
+    }
+
+    #[cfg(feature = "toml")]
+    fn parse_definitions_toml(
+        &self,
+        source: &str,
+        redefine: bool,
+    ) -> Result<Vec<Definition>, DefinitionError> {
+        self.parse_definitions(redefine, |seed| {
+            seed.deserialize(toml::Deserializer::new(source))
+        })
+        .map_err(|(error, kind)| {
+            let definition_error = DefinitionError::new(kind, error.message());
+            match error.span() {
+                Some(span) => {
+                    let before = &source[..span.start];
+                    let line = before.matches('\n').count() + 1;
+                    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                    definition_error.at(line, column)
+                }
+                None => definition_error,
+            }
+        })
+    }
+
+    /// Reads the definitions from a file, in the format its extension names: `.json`, `.ron` or `.toml`.
+    /// Each format requires the crate feature of the same name.
+    ///
+    /// Parents defined in other files have to be loaded first.
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+    pub fn load_definitions_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<ItemTypeId>, DefinitionError> {
+        let definitions = self.parse_definitions_file(path.as_ref(), false)?;
+        Ok(self.register_definitions(definitions))
+    }
+
+    /// Like `reload_definitions`, but reads the definitions from a file like `load_definitions_file`.
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+    pub fn reload_definitions_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<ReloadReport, DefinitionError> {
+        let definitions = self.parse_definitions_file(path.as_ref(), true)?;
+        Ok(self.redefine(definitions))
+    }
+
+    #[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+    fn parse_definitions_file(
+        &self,
+        path: &Path,
+        redefine: bool,
+    ) -> Result<Vec<Definition>, DefinitionError> {
+        let in_file = |mut error: DefinitionError| {
+            error.file = Some(path.to_owned());
+            error
+        };
+        let source = std::fs::read_to_string(path).map_err(|error| {
+            in_file(DefinitionError::new(
+                DefinitionErrorKind::Io,
+                error.to_string(),
+            ))
+        })?;
+        let result = match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => self.parse_definitions_json(&source, redefine),
+            #[cfg(feature = "ron")]
+            Some("ron") => self.parse_definitions_ron(&source, redefine),
+            #[cfg(feature = "toml")]
+            Some("toml") => self.parse_definitions_toml(&source, redefine),
+            _ => Err(DefinitionError::new(
+                DefinitionErrorKind::UnsupportedFormat,
+                "unsupported definition format",
+            )),
+        };
+        result.map_err(in_file)
+    }
+}
+
+/// A list of definition files, which reloads the files that changed on disk while the game is running.
+///
+/// Changes are detected by the modification time of the files, so `reload_changed` can be called
+/// regularly, for example once a second or when the game window gets focused.
+///
+/// # Examples
+/// ```no_run
+/// use shrub::{DefinitionFiles, ItemRegistry};
+///
+/// let mut registry = ItemRegistry::new();
+/// let mut files = DefinitionFiles::new()
+///     .with_file("items/weapons.toml")
+///     .with_file("items/swords.toml");
+/// files.load(&mut registry).unwrap();
+///
+/// // later, in the game loop
+/// let (report, errors) = files.reload_changed(&mut registry);
+/// for change in report.changed() {
+///     println!("reloaded {:?}", registry.key_of(change.id()));
+/// }
+/// for error in errors {
+///     eprintln!("{error}");
+/// }
+/// ```
+#[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+#[derive(Debug, Default)]
+pub struct DefinitionFiles {
+    files: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+}
+
+#[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+impl DefinitionFiles {
+    /// Creates an empty list of definition files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the file and returns the list. Files are loaded in the order they were added,
+    /// so files defining parents have to be added before the files using them.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.add_file(path);
+        self
+    }
+
+    /// Adds the file, see `DefinitionFiles::with_file`.
+    pub fn add_file(&mut self, path: impl Into<PathBuf>) {
+        self.files.push((path.into(), None));
+    }
+
+    /// Iterates over the files in the order they are loaded.
+    pub fn files(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Loads all files into the registry with `ItemRegistry::load_definitions_file` and returns the ids of their item types.
+    /// Stops at the first file that fails to load.
+    pub fn load(
+        &mut self,
+        registry: &mut ItemRegistry,
+    ) -> Result<Vec<ItemTypeId>, DefinitionError> {
+        let mut ids = Vec::new();
+        for (path, modified) in &mut self.files {
+            let last_modified = modification_time(path);
+            ids.extend(registry.load_definitions_file(&*path)?);
+            *modified = last_modified;
+        }
+        Ok(ids)
+    }
+
+    /// Reloads the files that changed since they were last loaded with `ItemRegistry::reload_definitions_file`.
+    ///
+    /// Returns the combined report of all reloaded files together with the errors of the files that failed.
+    /// Failed files don't change the registry and are tried again by the next call.
+    pub fn reload_changed(
+        &mut self,
+        registry: &mut ItemRegistry,
+    ) -> (ReloadReport, Vec<DefinitionError>) {
+        let mut report = ReloadReport::default();
+        let mut errors = Vec::new();
+        for (path, modified) in &mut self.files {
+            let last_modified = modification_time(path);
+            if last_modified.is_some() && last_modified == *modified {
+                continue;
+            }
+            match registry.reload_definitions_file(&*path) {
+                Ok(file_report) => {
+                    report.extend(file_report);
+                    *modified = last_modified;
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        (report, errors)
+    }
+}
+
+#[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+fn modification_time(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{ItemData, ItemRegistry, MaxStackSize, Weight};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Damage {
+        min: u32,
+        max: u32,
+    }
+    impl ItemData for Damage {}
+
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::new();
+        registry.register_data::<Damage>("test:damage");
+        registry.add_tag_group("test:equipment", ["test:weapons"]);
+        registry
+    }
+
+    #[test]
+    fn load_definitions_with_parents() {
+        let mut registry = registry();
+        let definitions = r#"{
+            "test:weapon": {
+                "tags": ["test:weapons"],
+                "data": { "shrub:max_stack_size": 1, "test:damage": { "min": 1, "max": 2 } }
+            },
+            "test:sword": { "parent": "test:weapon", "data": { "shrub:weight": 3.0 } },
+            "test:stone": {}
+        }"#;
+        let ids = registry
+            .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let sword = &registry[ids[1]];
+        assert_eq!(sword.key(), Some("test:sword"));
+        assert!(std::ptr::eq(
+            sword.parent().unwrap().as_ref(),
+            registry[ids[0]].as_ref()
+        ));
+        assert!(sword.has_tag("test:equipment"));
+        assert_eq!(
+            sword.get_data::<Damage>().as_deref(),
+            Some(&Damage { min: 1, max: 2 })
+        );
+        assert_eq!(
+            sword.get_data::<MaxStackSize>().as_deref(),
+            Some(&MaxStackSize(1))
+        );
+        assert_eq!(sword.get_data::<Weight>().as_deref(), Some(&Weight(3.0)));
+        assert_eq!(registry[ids[2]].max_stack_size(), 1);
+
+        // parents can come from earlier files
+        let definitions = r#"{ "test:axe": { "parent": "test:weapon" } }"#;
+        let ids = registry
+            .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .unwrap();
+        assert!(registry[ids[0]].has_tag("test:weapons"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn report_json_errors_with_position() {
+        use crate::DefinitionErrorKind;
+
+        let cases = [
+            (
+                "{\n\"test:wand\": {\n\"data\": { \"test:mana\": 3 }\n}\n}",
+                DefinitionErrorKind::UnknownDataTag,
+                3,
+            ),
+            (
+                "{\n\"test:sword\": {\n\"data\": {\n\"test:damage\": { \"min\": -1 }\n}\n}\n}",
+                DefinitionErrorKind::Malformed,
+                4,
+            ),
+            (
+                "{\n\"test:sword\": { \"parent\": \"test:axe\" },\n\"test:axe\": {}\n}",
+                DefinitionErrorKind::UnknownParent,
+                2,
+            ),
+            (
+                "{\n\"test:axe\": {},\n\"test:axe\": {}\n}",
+                DefinitionErrorKind::DuplicateKey,
+                3,
+            ),
+            ("{\n\n\"Axe\": {}\n}", DefinitionErrorKind::InvalidKey, 3),
+            (
+                "{\n\"test:axe\": { \"colour\": \"red\" }\n}",
+                DefinitionErrorKind::Malformed,
+                2,
+            ),
+            ("{\n\"test:axe\": {}", DefinitionErrorKind::Malformed, 2),
+        ];
+        for (source, kind, line) in cases {
+            let mut registry = registry();
+            let error = registry.load_definitions_json(source).unwrap_err();
+            assert_eq!(error.kind(), kind, "{error}");
+            assert_eq!(error.line(), Some(line), "{error}");
+            assert!(registry.is_empty());
+        }
+
+        let error = registry()
+            .load_definitions_json("{\n\"test:wand\": {\n\"data\": { \"test:mana\": 3 }\n}\n}")
+            .unwrap_err();
+        assert_eq!(error.message(), "unknown item data tag `test:mana`");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "3:{}: unknown item data tag `test:mana`",
+                error.column().unwrap()
+            )
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn report_ron_errors_with_position() {
+        use crate::DefinitionErrorKind;
+
+        let mut registry = registry();
+        let error = registry
+            .load_definitions_ron("{\n\"test:wand\": (\n  data: { \"test:mana\": 3 },\n),\n}")
+            .unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::UnknownDataTag);
+        assert_eq!(error.line(), Some(3));
+
+        let error = registry
+            .load_definitions_ron(
+                "{\n\"test:sword\": (\n  data: { \"shrub:weight\": \"heavy\" },\n),\n}",
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::Malformed);
+        assert_eq!(error.line(), Some(3));
+        assert!(registry.is_empty());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn report_toml_errors_with_position() {
+        use crate::DefinitionErrorKind;
+
+        let mut registry = registry();
+        let error = registry
+            .load_definitions_toml(
+                "[\"test:wand\"]\ntags = []\n\n[\"test:wand\".data]\n\"test:mana\" = 3\n",
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::UnknownDataTag);
+        assert_eq!(error.line(), Some(4));
+
+        let error = registry
+            .load_definitions_toml("[\"test:sword\"]\nparent = \"test:weapon\"\n")
+            .unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::UnknownParent);
+        assert_eq!(error.line(), Some(1));
+        assert!(registry.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_definitions_from_files() {
+        use crate::DefinitionErrorKind;
+
+        let directory =
+            std::env::temp_dir().join(format!("shrub-definitions-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let weapons = directory.join("weapons.json");
+        std::fs::write(
+            &weapons,
+            r#"{ "test:sword": { "data": { "test:mana": 3 } } }"#,
+        )
+        .unwrap();
+        let notes = directory.join("weapons.txt");
+        std::fs::write(&notes, "swords are sharp").unwrap();
+
+        let mut registry = registry();
+        let error = registry.load_definitions_file(&weapons).unwrap_err();
+        assert_eq!(error.file(), Some(weapons.as_path()));
+        assert!(error
+            .to_string()
+            .starts_with(&format!("{}:1:", weapons.display())));
+        let error = registry.load_definitions_file(&notes).unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::UnsupportedFormat);
+        let error = registry
+            .load_definitions_file(directory.join("missing.json"))
+            .unwrap_err();
+        assert_eq!(error.kind(), DefinitionErrorKind::Io);
+        assert_eq!(error.line(), None);
+
+        std::fs::write(
+            &weapons,
+            r#"{ "test:sword": { "data": { "test:damage": { "min": 2, "max": 5 } } } }"#,
+        )
+        .unwrap();
+        let ids = registry.load_definitions_file(&weapons).unwrap();
+        assert_eq!(registry.key_of(ids[0]), Some("test:sword"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn reload_changed_files() {
+        use std::time::{Duration, SystemTime};
+
+        use crate::DefinitionFiles;
+
+        let directory = std::env::temp_dir().join(format!("shrub-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let weapons = directory.join("weapons.json");
+        let swords = directory.join("swords.json");
+        std::fs::write(
+            &weapons,
+            r#"{ "test:weapon": { "data": { "shrub:weight": 1.0 } } }"#,
+        )
+        .unwrap();
+        std::fs::write(&swords, r#"{ "test:sword": { "parent": "test:weapon" } }"#).unwrap();
+        // modification times can be coarse, so they are moved forward explicitly
+        let touch = |path: &std::path::Path, seconds| {
+            let time = SystemTime::now() + Duration::from_secs(seconds);
+            let file = std::fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(time).unwrap();
+        };
+
+        let mut registry = registry();
+        let mut files = DefinitionFiles::new()
+            .with_file(&weapons)
+            .with_file(&swords);
+        let ids = files.load(&mut registry).unwrap();
+        let sword = registry[ids[1]].item_new();
+        let (report, errors) = files.reload_changed(&mut registry);
+        assert!(report.is_empty() && errors.is_empty());
+
+        std::fs::write(
+            &weapons,
+            r#"{ "test:weapon": { "data": { "shrub:weight": 2.0 } } }"#,
+        )
+        .unwrap();
+        touch(&weapons, 10);
+        std::fs::write(&swords, r#"{ "test:sword": { "parent": "test:shield" } }"#).unwrap();
+        touch(&swords, 10);
+        let (report, errors) = files.reload_changed(&mut registry);
+        assert_eq!(
+            report
+                .changed()
+                .map(|change| change.id())
+                .collect::<Vec<_>>(),
+            vec![ids[0]]
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file(), Some(swords.as_path()));
+        assert_eq!(sword.get_data::<Weight>().as_deref(), Some(&Weight(2.0)));
+
+        // the failed file is tried again
+        std::fs::write(
+            &swords,
+            r#"{ "test:sword": { "parent": "test:weapon", "tags": ["test:blades"] } }"#,
+        )
+        .unwrap();
+        touch(&swords, 20);
+        let (report, errors) = files.reload_changed(&mut registry);
+        assert!(errors.is_empty());
+        assert_eq!(
+            report
+                .changed()
+                .map(|change| change.id())
+                .collect::<Vec<_>>(),
+            vec![ids[1]]
+        );
+        assert!(sword.item_type().has_tag("test:blades"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn reload_definitions_in_place() {
+        let mut registry = registry();
+        let definitions = r#"{
+            "test:weapon": { "tags": ["test:weapons"], "data": { "test:damage": { "min": 1, "max": 2 } } },
+            "test:sword": { "parent": "test:weapon", "data": { "shrub:weight": 3.0 } },
+            "test:stone": { "data": { "shrub:weight": 1.0 } }
+        }"#;
+        let ids = registry
+            .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .unwrap();
+        let old_weapon = registry[ids[0]].clone();
+        let sword = registry[ids[1]].item_new();
+
+        let definitions = r#"{
+            "test:weapon": { "tags": ["test:weapons"], "data": { "test:damage": { "min": 4, "max": 8 } } },
+            "test:sword": { "parent": "test:weapon", "tags": ["test:blades"], "data": { "shrub:max_stack_size": 1 } },
+            "test:stone": { "data": { "shrub:weight": 1.0 } },
+            "test:axe": { "parent": "test:weapon" }
+        }"#;
+        let report = registry
+            .reload_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .unwrap();
+
+        let axe = registry.id_of("test:axe").unwrap();
+        assert_eq!(report.added().collect::<Vec<_>>(), vec![axe]);
+        let changes: Vec<_> = report.changed().collect();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].id(), ids[0]);
+        assert_eq!(changes[0].data().collect::<Vec<_>>(), vec!["test:damage"]);
+        assert!(!changes[0].tags_changed() && !changes[0].parent_changed());
+        assert_eq!(changes[1].id(), ids[1]);
+        assert_eq!(
+            changes[1].data().collect::<Vec<_>>(),
+            vec!["shrub:max_stack_size", "shrub:weight"]
+        );
+        assert!(changes[1].tags_changed());
+
+        // live items and old handles see the new definitions, also through their parents
+        assert_eq!(
+            sword.get_data::<Damage>().as_deref(),
+            Some(&Damage { min: 4, max: 8 })
+        );
+        assert_eq!(sword.get_data::<Weight>().as_deref(), None);
+        assert!(sword.item_type().has_tag("test:blades"));
+        assert!(sword.can_stack_with(&registry[ids[1]].item_new()));
+        assert!(Arc::ptr_eq(&old_weapon, &registry[ids[0]]));
+        assert_eq!(
+            old_weapon.get_data::<Damage>().as_deref(),
+            Some(&Damage { min: 4, max: 8 })
+        );
+        assert_eq!(registry.len(), 4);
+    }
+
+    #[test]
+    fn reject_parent_cycles() {
+        let mut registry = registry();
+        let definitions = r#"{ "test:weapon": {}, "test:sword": { "parent": "test:weapon" } }"#;
+        registry
+            .load_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .unwrap();
+
+        let definitions = r#"{ "test:weapon": { "parent": "test:sword" } }"#;
+        assert!(registry
+            .reload_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .is_err());
+        let definitions = r#"{ "test:axe": { "parent": "test:weapon" }, "test:weapon": { "parent": "test:axe" } }"#;
+        assert!(registry
+            .reload_definitions(&mut serde_json::Deserializer::from_str(definitions))
+            .is_err());
+        assert!(registry
+            .get_by_key("test:weapon")
+            .unwrap()
+            .parent()
+            .is_none());
+        assert!(!registry.contains_key("test:axe"));
+
+        #[cfg(feature = "json")]
+        {
+            let error = registry
+                .parse_definitions_json(r#"{ "test:weapon": { "parent": "test:sword" } }"#, true)
+                .err()
+                .unwrap();
+            assert_eq!(error.kind(), crate::DefinitionErrorKind::ParentCycle);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use crate::Item;
+
+/// Reasons why an operation on an inventory or on item data failed.
+///
+/// Errors of operations that take an item hand it back, so it can be recovered with `ShrubError::into_item`.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{ItemType, ShrubError, SlotInventory};
+///
+/// let item_type = Arc::new(ItemType::new());
+/// let mut inventory = SlotInventory::new(1);
+/// inventory.try_insert_item(0, item_type.item_new()).unwrap();
+///
+/// match inventory.try_insert_item(0, item_type.item_new()) {
+///     Err(ShrubError::SlotOccupied(item)) => drop(item), // put it somewhere else
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum ShrubError {
+    /// The inventory has no room left for the item. When a part of a stack fit, only the remainder is returned.
+    InventoryFull(Item),
+    /// The selected slot already holds another item.
+    SlotOccupied(Item),
+    /// The selected slot doesn't exist in the inventory.
+    InvalidSlot(Item),
+    /// The inventory doesn't accept this kind of item.
+    NotAllowed(Item),
+    /// There is no item at the selected slot.
+    SlotEmpty,
+    /// The stack can't be split, see `Item::split`.
+    CannotSplit,
+    /// The item doesn't carry data of the requested type.
+    DataMissing {
+        /// Name of the requested data type, as returned by `std::any::type_name`.
+        type_name: &'static str,
+    },
+}
+
+/// The kind of a `ShrubError`, without the item it hands back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShrubErrorKind {
+    /// See `ShrubError::InventoryFull`.
+    InventoryFull,
+    /// See `ShrubError::SlotOccupied`.
+    SlotOccupied,
+    /// See `ShrubError::InvalidSlot`.
+    InvalidSlot,
+    /// See `ShrubError::NotAllowed`.
+    NotAllowed,
+    /// See `ShrubError::SlotEmpty`.
+    SlotEmpty,
+    /// See `ShrubError::CannotSplit`.
+    CannotSplit,
+    /// See `ShrubError::DataMissing`.
+    DataMissing,
+}
+
+impl ShrubError {
+    /// Creates a `DataMissing` error for the given data type.
+    pub(crate) fn data_missing<D>() -> Self {
+        ShrubError::DataMissing {
+            type_name: std::any::type_name::<D>(),
+        }
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> ShrubErrorKind {
+        match self {
+            ShrubError::InventoryFull(_) => ShrubErrorKind::InventoryFull,
+            ShrubError::SlotOccupied(_) => ShrubErrorKind::SlotOccupied,
+            ShrubError::InvalidSlot(_) => ShrubErrorKind::InvalidSlot,
+            ShrubError::NotAllowed(_) => ShrubErrorKind::NotAllowed,
+            ShrubError::SlotEmpty => ShrubErrorKind::SlotEmpty,
+            ShrubError::CannotSplit => ShrubErrorKind::CannotSplit,
+            ShrubError::DataMissing { .. } => ShrubErrorKind::DataMissing,
+        }
+    }
+
+    /// Borrows the item which the failed operation handed back.
+    pub fn item(&self) -> Option<&Item> {
+        match self {
+            ShrubError::InventoryFull(item)
+            | ShrubError::SlotOccupied(item)
+            | ShrubError::InvalidSlot(item)
+            | ShrubError::NotAllowed(item) => Some(item),
+            ShrubError::SlotEmpty | ShrubError::CannotSplit | ShrubError::DataMissing { .. } => {
+                None
+            }
+        }
+    }
+
+    /// Takes the item which the failed operation handed back.
+    pub fn into_item(self) -> Option<Item> {
+        match self {
+            ShrubError::InventoryFull(item)
+            | ShrubError::SlotOccupied(item)
+            | ShrubError::InvalidSlot(item)
+            | ShrubError::NotAllowed(item) => Some(item),
+            ShrubError::SlotEmpty | ShrubError::CannotSplit | ShrubError::DataMissing { .. } => {
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for ShrubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShrubError::InventoryFull(_) => f.write_str("the inventory is full"),
+            ShrubError::SlotOccupied(_) => f.write_str("the slot is already occupied"),
+            ShrubError::InvalidSlot(_) => f.write_str("the slot doesn't exist"),
+            ShrubError::NotAllowed(_) => f.write_str("the item isn't allowed in the inventory"),
+            ShrubError::SlotEmpty => f.write_str("there is no item in the slot"),
+            ShrubError::CannotSplit => f.write_str("the stack can't be split"),
+            ShrubError::DataMissing { type_name } => {
+                write!(f, "the item has no data of type `{type_name}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShrubError {}
@@ -0,0 +1,152 @@
+use std::{fmt, sync::Arc};
+
+use crate::{Item, ItemData, ItemType};
+
+/// A predicate that decides which items an inventory or slot accepts.
+///
+/// Filters are cheap to clone, so the same filter can be shared by many slots.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{ItemData, ItemFilter, ItemType};
+///
+/// struct Helmet;
+/// impl ItemData for Helmet {}
+/// struct Cursed;
+/// impl ItemData for Cursed {}
+///
+/// let filter = ItemFilter::has_data::<Helmet>().and(!ItemFilter::has_data::<Cursed>());
+/// let helmet = Arc::new(ItemType::with_data(Helmet));
+/// assert!(filter.matches(&helmet.item_new()));
+/// assert!(!filter.matches(&helmet.item_with_data(Cursed)));
+/// ```
+#[derive(Clone)]
+pub struct ItemFilter(Arc<dyn Fn(&Item) -> bool + Send + Sync>);
+
+impl ItemFilter {
+    /// Creates a filter that accepts the items for which the predicate returns `true`.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Item) -> bool + Send + Sync + 'static,
+    {
+        ItemFilter(Arc::new(predicate))
+    }
+
+    /// Creates a filter that accepts every item.
+    pub fn any() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Creates a filter that accepts the items carrying data of type `D`, on the item or its type.
+    pub fn has_data<D: ItemData>() -> Self {
+        Self::new(|item| item.get_data::<D>().is_some())
+    }
+
+    /// Creates a filter that accepts the items of the given types, compared like `ItemType`s compare with each other.
+    pub fn item_types<T>(item_types: T) -> Self
+    where
+        T: IntoIterator<Item = Arc<ItemType>>,
+    {
+        let item_types: Vec<_> = item_types.into_iter().collect();
+        Self::new(move |item| {
+            item_types
+                .iter()
+                .any(|item_type| **item_type == **item.item_type())
+        })
+    }
+
+    /// Creates a filter that accepts the items whose type has the tag, see `ItemType::has_tag`.
+    pub fn tag(tag: &str) -> Self {
+        let tag: Box<str> = tag.into();
+        Self::new(move |item| item.item_type().has_tag(&tag))
+    }
+
+    /// Creates a filter that accepts the items whose type has at least one of the tags.
+    pub fn any_tag<'a>(tags: impl IntoIterator<Item = &'a str>) -> Self {
+        let tags: Vec<Box<str>> = tags.into_iter().map(Into::into).collect();
+        Self::new(move |item| item.item_type().any_of(tags.iter().map(|tag| &**tag)))
+    }
+
+    /// Creates a filter that accepts the items whose type has all of the tags.
+    pub fn all_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Self {
+        let tags: Vec<Box<str>> = tags.into_iter().map(Into::into).collect();
+        Self::new(move |item| item.item_type().all_of(tags.iter().map(|tag| &**tag)))
+    }
+
+    /// Returns `true` when the filter accepts the item.
+    #[inline]
+    pub fn matches(&self, item: &Item) -> bool {
+        (self.0)(item)
+    }
+
+    /// Accepts the items that both filters accept.
+    pub fn and(self, other: ItemFilter) -> Self {
+        Self::new(move |item| self.matches(item) && other.matches(item))
+    }
+
+    /// Accepts the items that either filter accepts.
+    pub fn or(self, other: ItemFilter) -> Self {
+        Self::new(move |item| self.matches(item) || other.matches(item))
+    }
+}
+
+impl std::ops::Not for ItemFilter {
+    type Output = ItemFilter;
+
+    /// Accepts the items that the filter rejects.
+    fn not(self) -> Self {
+        Self::new(move |item| !self.matches(item))
+    }
+}
+
+impl fmt::Debug for ItemFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ItemFilter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{ItemData, ItemFilter, ItemType};
+
+    struct Arrow;
+    impl ItemData for Arrow {}
+
+    #[test]
+    fn combine_filters() {
+        let arrow = Arc::new(ItemType::with_data(Arrow));
+        let bolt = Arc::new(ItemType::with_data(Arrow));
+        let stone = Arc::new(ItemType::new());
+
+        let arrows = ItemFilter::has_data::<Arrow>();
+        assert!(arrows.matches(&bolt.item_new()));
+        assert!(!arrows.matches(&stone.item_new()));
+
+        let only_arrows = arrows.clone().and(ItemFilter::item_types([arrow.clone()]));
+        assert!(only_arrows.matches(&arrow.item_new()));
+        assert!(!only_arrows.matches(&bolt.item_new()));
+
+        let ammo = only_arrows.or(ItemFilter::item_types([stone.clone()]));
+        assert!(ammo.matches(&stone.item_new()));
+        assert!(!(!ammo.clone()).matches(&stone.item_new()));
+        assert!(ItemFilter::any().matches(&bolt.item_new()));
+    }
+
+    #[test]
+    fn filter_by_tags() {
+        let arrow = Arc::new(
+            ItemType::new()
+                .with_tag("core:ammo")
+                .with_tag("core:arrows"),
+        );
+        let bolt = Arc::new(ItemType::new().with_tag("core:ammo"));
+
+        assert!(ItemFilter::tag("#core:ammo").matches(&bolt.item_new()));
+        assert!(!ItemFilter::tag("core:arrows").matches(&bolt.item_new()));
+        assert!(ItemFilter::any_tag(["core:arrows", "core:bolts"]).matches(&arrow.item_new()));
+        assert!(!ItemFilter::all_tags(["core:ammo", "core:arrows"]).matches(&bolt.item_new()));
+    }
+}
@@ -0,0 +1,786 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    itemdata::impl_value_item_data, AddReceipt, Inventory, InventoryEntries, InventoryItems,
+    InventorySelector, Item, ItemFilter, ShrubError, TransactionInventory,
+};
+
+/// How many cells of a `GridInventory` an item covers.
+/// Usually added to an `ItemType`, but single items can override it with their own data.
+/// Items without a Footprint cover a single cell.
+///
+/// # Examples
+/// ```
+/// use shrub::{Footprint, ItemType};
+///
+/// let rifle = ItemType::with_data(Footprint::rotatable(4, 1));
+/// assert_eq!(rifle.get_data::<Footprint>().unwrap().size(true), (1, 4));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Footprint {
+    /// Amount of columns the unrotated item covers.
+    pub width: u32,
+    /// Amount of rows the unrotated item covers.
+    pub height: u32,
+    /// Whether the item may be turned by 90 degrees, which swaps its width and height.
+    pub rotatable: bool,
+}
+
+impl Footprint {
+    /// Creates a Footprint of the given size which can't be rotated.
+    pub fn new(width: u32, height: u32) -> Self {
+        Footprint {
+            width,
+            height,
+            rotatable: false,
+        }
+    }
+
+    /// Creates a Footprint of the given size which can be rotated.
+    pub fn rotatable(width: u32, height: u32) -> Self {
+        Footprint {
+            width,
+            height,
+            rotatable: true,
+        }
+    }
+
+    /// Returns the width and height of the footprint, swapped when it is rotated.
+    #[inline]
+    pub fn size(&self, rotated: bool) -> (u32, u32) {
+        if rotated {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Footprint::new(1, 1)
+    }
+}
+
+impl_value_item_data!(Footprint);
+
+/// A 2D Inventory where every item covers the cells of its `Footprint`, selected by the `(x, y)` cell of its top left corner.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{Footprint, GridInventory, Inventory, InventorySelector, ItemType};
+///
+/// let rifle = Arc::new(ItemType::with_data(Footprint::rotatable(4, 1)));
+/// let mut inventory = GridInventory::new(3, 4);
+///
+/// // the rifle is too wide for the grid, so it is placed rotated
+/// assert!(inventory.add_item(rifle.item_new()).is_none());
+/// assert_eq!(inventory.is_rotated((0, 0)), Some(true));
+/// let (origin, _rifle) = inventory.item_at((0, 3)).unwrap();
+/// assert_eq!(origin, (0, 0));
+/// assert!(inventory.get_item((0, 3)).is_none()); // selectors only point at the top left cell
+/// ```
+pub struct GridInventory {
+    width: u32,
+    height: u32,
+    /// The top left cell of the item covering each cell, row by row.
+    cells: Vec<Option<(u32, u32)>>,
+    /// Placed items by their top left cell as `(y, x)`, so they are ordered row by row.
+    items: BTreeMap<(u32, u32), GridItem>,
+    filter: Option<ItemFilter>,
+}
+
+struct GridItem {
+    item: Item,
+    rotated: bool,
+    /// The cells the item covers, as its footprint can change after it was placed.
+    size: (u32, u32),
+}
+
+impl GridItem {
+    fn new(item: Item, rotated: bool) -> Self {
+        let size = footprint_of(&item).size(rotated);
+        GridItem {
+            item,
+            rotated,
+            size,
+        }
+    }
+}
+
+impl GridInventory {
+    /// Creates a new empty GridInventory with the given amount of columns and rows.
+    pub fn new(width: u32, height: u32) -> Self {
+        GridInventory {
+            width,
+            height,
+            cells: vec![None; width as usize * height as usize],
+            items: BTreeMap::new(),
+            filter: None,
+        }
+    }
+
+    /// Restricts the inventory to the items the filter accepts. Filters aren't serialized.
+    pub fn with_filter(mut self, filter: ItemFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Returns the filter of the inventory.
+    #[inline]
+    pub fn filter(&self) -> Option<&ItemFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Changes the filter of the inventory. Items it doesn't accept anymore stay in the inventory.
+    #[inline]
+    pub fn set_filter(&mut self, filter: Option<ItemFilter>) {
+        self.filter = filter;
+    }
+
+    /// Returns `true` when the filter of the inventory accepts the item.
+    pub fn accepts(&self, item: &Item) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(item))
+    }
+
+    /// Returns the amount of columns of the grid.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the amount of rows of the grid.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the amount of placed items.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` when no item is placed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns `true` when the cell exists and no item covers it.
+    pub fn is_cell_free(&self, (x, y): (u32, u32)) -> bool {
+        x < self.width && y < self.height && self.cells[self.index(x, y)].is_none()
+    }
+
+    /// Borrows the item covering the cell together with its top left cell.
+    pub fn item_at(&self, (x, y): (u32, u32)) -> Option<((u32, u32), &Item)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let origin = self.cells[self.index(x, y)]?;
+        Some((origin, &self.items[&(origin.1, origin.0)].item))
+    }
+
+    /// Returns whether the item placed at the selector is rotated.
+    pub fn is_rotated(&self, (x, y): (u32, u32)) -> Option<bool> {
+        Some(self.items.get(&(y, x))?.rotated)
+    }
+
+    /// Returns `true` when the inventory accepts the item and it could be placed at the selector without overlapping other items.
+    pub fn can_place(&self, item: &Item, origin: (u32, u32), rotated: bool) -> bool {
+        self.accepts(item)
+            && size_of(item, rotated).is_some_and(|size| self.fits(origin, size, None))
+    }
+
+    /// Finds the first place, row by row, where the item fits without overlapping other items.
+    /// Rotatable items are tried rotated when they don't fit unrotated at a place.
+    pub fn first_fit(&self, item: &Item) -> Option<((u32, u32), bool)> {
+        let footprint = footprint_of(item);
+        let orientations: &[bool] = if footprint.rotatable {
+            &[false, true]
+        } else {
+            &[false]
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for &rotated in orientations {
+                    if self.fits((x, y), footprint.size(rotated), None) {
+                        return Some(((x, y), rotated));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Puts an unrotated item with its top left corner at the selector.
+    /// * when the item doesn't fit there, it will return the item in `Some(Item)`
+    pub fn insert_item(&mut self, origin: (u32, u32), item: Item) -> Option<Item> {
+        self.try_insert_item(origin, item, false)
+            .err()
+            .and_then(ShrubError::into_item)
+    }
+
+    /// Puts an item with its top left corner at the selector, rotated or not.
+    /// Fails with `ShrubError::InvalidSlot` when the item reaches out of the grid, `ShrubError::SlotOccupied` when it
+    /// overlaps another item and `ShrubError::NotAllowed` when it should be rotated but its footprint isn't rotatable
+    /// or the filter of the inventory doesn't accept it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Footprint, GridInventory, ItemType, ShrubError};
+    ///
+    /// let crate_type = Arc::new(ItemType::with_data(Footprint::new(2, 2)));
+    /// let mut inventory = GridInventory::new(3, 3);
+    /// inventory.try_insert_item((0, 0), crate_type.item_new(), false).unwrap();
+    ///
+    /// let error = inventory.try_insert_item((1, 1), crate_type.item_new(), false).unwrap_err();
+    /// assert!(matches!(error, ShrubError::SlotOccupied(_)));
+    /// let error = inventory.try_insert_item((2, 0), crate_type.item_new(), false).unwrap_err();
+    /// assert!(matches!(error, ShrubError::InvalidSlot(_)));
+    /// ```
+    pub fn try_insert_item(
+        &mut self,
+        origin: (u32, u32),
+        item: Item,
+        rotated: bool,
+    ) -> Result<(), ShrubError> {
+        let Some(size) = size_of(&item, rotated).filter(|_| self.accepts(&item)) else {
+            return Err(ShrubError::NotAllowed(item));
+        };
+        if !self.in_bounds(origin, size) {
+            return Err(ShrubError::InvalidSlot(item));
+        }
+        if !self.fits(origin, size, None) {
+            return Err(ShrubError::SlotOccupied(item));
+        }
+        self.place(origin, GridItem::new(item, rotated));
+        Ok(())
+    }
+
+    /// Turns the item at the selector by 90 degrees around its top left cell.
+    /// Returns `false` and leaves the inventory untouched when there is no rotatable item or it doesn't fit rotated.
+    pub fn rotate_item(&mut self, origin: (u32, u32)) -> bool {
+        let Some(entry) = self.items.get(&(origin.1, origin.0)) else {
+            return false;
+        };
+        let size = (entry.size.1, entry.size.0);
+        if !footprint_of(&entry.item).rotatable || !self.fits(origin, size, Some(origin)) {
+            return false;
+        }
+        let mut entry = self.take(origin).unwrap();
+        entry.rotated = !entry.rotated;
+        entry.size = size;
+        self.place(origin, entry);
+        true
+    }
+
+    /// Moves the item at selector `from` so its top left corner is at `to`, keeping its rotation.
+    /// Returns `false` and leaves the inventory untouched when there is no item at `from` or it doesn't fit at `to`.
+    pub fn move_item(&mut self, from: (u32, u32), to: (u32, u32)) -> bool {
+        let Some(entry) = self.items.get(&(from.1, from.0)) else {
+            return false;
+        };
+        if !self.fits(to, entry.size, Some(from)) {
+            return false;
+        }
+        let entry = self.take(from).unwrap();
+        self.place(to, entry);
+        true
+    }
+
+    /// Iterates over all items row by row, together with their top left cell.
+    pub fn iter(&self) -> impl Iterator<Item = ((u32, u32), &Item)> + '_ {
+        self.items
+            .iter()
+            .map(|(&(y, x), entry)| ((x, y), &entry.item))
+    }
+
+    /// Iterates mutably over all items row by row, together with their top left cell.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((u32, u32), &mut Item)> + '_ {
+        self.items
+            .iter_mut()
+            .map(|(&(y, x), entry)| ((x, y), &mut entry.item))
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    fn in_bounds(&self, (x, y): (u32, u32), (width, height): (u32, u32)) -> bool {
+        x.checked_add(width)
+            .is_some_and(|right| right <= self.width)
+            && y.checked_add(height)
+                .is_some_and(|bottom| bottom <= self.height)
+    }
+
+    /// Checks that the area is inside of the grid and only covered by the item at `ignore`, if any.
+    fn fits(&self, origin: (u32, u32), size: (u32, u32), ignore: Option<(u32, u32)>) -> bool {
+        self.in_bounds(origin, size)
+            && (origin.1..origin.1 + size.1).all(|y| {
+                (origin.0..origin.0 + size.0).all(|x| match self.cells[self.index(x, y)] {
+                    None => true,
+                    covering => covering == ignore,
+                })
+            })
+    }
+
+    fn set_cells(&mut self, origin: (u32, u32), size: (u32, u32), value: Option<(u32, u32)>) {
+        for y in origin.1..origin.1 + size.1 {
+            for x in origin.0..origin.0 + size.0 {
+                let index = self.index(x, y);
+                self.cells[index] = value;
+            }
+        }
+    }
+
+    fn place(&mut self, origin: (u32, u32), entry: GridItem) {
+        self.set_cells(origin, entry.size, Some(origin));
+        self.items.insert((origin.1, origin.0), entry);
+    }
+
+    fn take(&mut self, origin: (u32, u32)) -> Option<GridItem> {
+        let entry = self.items.remove(&(origin.1, origin.0))?;
+        self.set_cells(origin, entry.size, None);
+        Some(entry)
+    }
+}
+
+/// Returns the footprint of the item, which defaults to a single cell.
+fn footprint_of(item: &Item) -> Footprint {
+    item.get_data::<Footprint>()
+        .map_or_else(Footprint::default, |footprint| *footprint)
+}
+
+/// Returns the size of the item in the given orientation, or `None` when it can't be rotated.
+fn size_of(item: &Item, rotated: bool) -> Option<(u32, u32)> {
+    let footprint = footprint_of(item);
+    (!rotated || footprint.rotatable).then(|| footprint.size(rotated))
+}
+
+impl Inventory for GridInventory {
+    /// Merges the item into existing compatible stacks first and puts the remainder at the first place it fits,
+    /// see `GridInventory::first_fit`. When it doesn't fit anywhere, only the remainder is returned.
+    fn add_item(&mut self, mut item: Item) -> Option<Item> {
+        if !self.accepts(&item) {
+            return Some(item);
+        }
+        for entry in self.items.values_mut() {
+            item = entry.item.merge(item)?;
+        }
+        match self.first_fit(&item) {
+            Some((origin, rotated)) => {
+                self.place(origin, GridItem::new(item, rotated));
+                None
+            }
+            None => Some(item),
+        }
+    }
+
+    /// Like `add_item`, but fails with `ShrubError::NotAllowed` when the filter doesn't accept the item.
+    fn try_add_item(&mut self, item: Item) -> Result<(), ShrubError> {
+        if !self.accepts(&item) {
+            return Err(ShrubError::NotAllowed(item));
+        }
+        match self.add_item(item) {
+            Some(item) => Err(ShrubError::InventoryFull(item)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl InventorySelector<(u32, u32)> for GridInventory {
+    fn get_item(&self, (x, y): (u32, u32)) -> Option<&Item> {
+        Some(&self.items.get(&(y, x))?.item)
+    }
+
+    fn get_item_mut(&mut self, (x, y): (u32, u32)) -> Option<&mut Item> {
+        Some(&mut self.items.get_mut(&(y, x))?.item)
+    }
+
+    fn remove_item(&mut self, selector: (u32, u32)) -> Option<Item> {
+        Some(self.take(selector)?.item)
+    }
+}
+
+impl InventoryItems for GridInventory {
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.items.values().map(|entry| &entry.item)
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.items.values_mut().map(|entry| &mut entry.item)
+    }
+}
+
+impl InventoryEntries<(u32, u32)> for GridInventory {
+    const STABLE_SELECTORS: bool = true;
+
+    fn entries(&self) -> impl Iterator<Item = ((u32, u32), &Item)> {
+        self.items
+            .iter()
+            .map(|(&(y, x), entry)| ((x, y), &entry.item))
+    }
+}
+
+impl TransactionInventory<(u32, u32)> for GridInventory {
+    fn add_item_tracked(&mut self, mut item: Item) -> Result<AddReceipt<(u32, u32)>, ShrubError> {
+        if !self.accepts(&item) {
+            return Err(ShrubError::NotAllowed(item));
+        }
+        let fit = self.first_fit(&item);
+        if fit.is_none() {
+            let room: u32 = self
+                .iter()
+                .filter(|(_, stack)| stack.can_stack_with(&item))
+                .map(|(_, stack)| stack.max_stack_size().saturating_sub(stack.count()))
+                .fold(0, u32::saturating_add);
+            if room < item.count() {
+                return Err(ShrubError::InventoryFull(item));
+            }
+        }
+
+        let mut merged = Vec::new();
+        for (origin, stack) in self.iter_mut() {
+            if item.count() == 0 {
+                break;
+            }
+            match stack.take_from(&mut item) {
+                0 => {}
+                count => merged.push((origin, count)),
+            }
+        }
+        if item.count() == 0 {
+            return Ok(AddReceipt::merged(merged, item));
+        }
+        let (origin, rotated) = fit.unwrap();
+        self.place(origin, GridItem::new(item, rotated));
+        Ok(AddReceipt::placed(merged, origin))
+    }
+
+    /// Puts the item at the selector unrotated, or rotated when it only fits that way.
+    fn insert_item(&mut self, selector: (u32, u32), item: Item) -> Option<Item> {
+        let item = GridInventory::insert_item(self, selector, item)?;
+        self.try_insert_item(selector, item, true)
+            .err()
+            .and_then(ShrubError::into_item)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialization {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{GridInventory, GridItem};
+    use crate::{load::unknown_policy, Item, UnknownPolicy};
+
+    #[derive(Serialize)]
+    struct GridRef<'a> {
+        width: u32,
+        height: u32,
+        items: Vec<EntryRef<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct EntryRef<'a> {
+        x: u32,
+        y: u32,
+        rotated: bool,
+        item: &'a Item,
+    }
+
+    #[derive(Deserialize)]
+    struct Grid {
+        width: u32,
+        height: u32,
+        items: Vec<Entry>,
+    }
+
+    #[derive(Deserialize)]
+    struct Entry {
+        x: u32,
+        y: u32,
+        rotated: bool,
+        item: Item,
+    }
+
+    impl Serialize for GridInventory {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            GridRef {
+                width: self.width,
+                height: self.height,
+                items: self
+                    .items
+                    .iter()
+                    .map(|(&(y, x), entry)| EntryRef {
+                        x,
+                        y,
+                        rotated: entry.rotated,
+                        item: &entry.item,
+                    })
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for GridInventory {
+        /// Items of missing types are left out when they are dropped, see `UnknownPolicy::Drop`.
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let grid = Grid::deserialize(deserializer)?;
+            let drop_placeholders = unknown_policy() == UnknownPolicy::Drop;
+            let mut inventory = GridInventory::new(grid.width, grid.height);
+            for Entry {
+                x,
+                y,
+                rotated,
+                item,
+            } in grid.items
+            {
+                if drop_placeholders && item.item_type().is_placeholder() {
+                    continue;
+                }
+                if !inventory.can_place(&item, (x, y), rotated) {
+                    return Err(de::Error::custom(format_args!(
+                        "the item at ({x}, {y}) overlaps another item or reaches out of the grid"
+                    )));
+                }
+                inventory.place((x, y), GridItem::new(item, rotated));
+            }
+            Ok(inventory)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        transfer, Footprint, GridInventory, Inventory, InventorySelector, ItemFilter, ItemType,
+        MaxStackSize, ShrubError, SlotInventory, Transaction,
+    };
+
+    #[test]
+    fn filter_items() {
+        let crate_type = Arc::new(ItemType::with_data(Footprint::new(2, 2)));
+        let gem = Arc::new(ItemType::new());
+        let mut inventory =
+            GridInventory::new(3, 3).with_filter(ItemFilter::has_data::<Footprint>());
+
+        assert!(!inventory.accepts(&gem.item_new()));
+        assert!(inventory.add_item(gem.item_new()).is_some());
+        assert!(matches!(
+            inventory.try_add_item(gem.item_new()),
+            Err(ShrubError::NotAllowed(_))
+        ));
+        assert!(matches!(
+            inventory.try_insert_item((2, 2), gem.item_new(), false),
+            Err(ShrubError::NotAllowed(_))
+        ));
+        assert!(inventory.add_item(crate_type.item_new()).is_none());
+        assert!(inventory.is_cell_free((2, 2)));
+    }
+
+    #[test]
+    fn keep_the_placed_size_when_the_footprint_changes() {
+        let crate_type = Arc::new(ItemType::with_data(Footprint::new(2, 2)));
+        let gem = Arc::new(ItemType::new());
+        let mut inventory = GridInventory::new(4, 4);
+
+        assert!(inventory
+            .insert_item((0, 0), crate_type.item_new())
+            .is_none());
+        inventory
+            .get_item_mut((0, 0))
+            .unwrap()
+            .add_data(Footprint::new(1, 1));
+        assert!(inventory.item_at((1, 1)).is_some());
+        assert!(inventory.move_item((0, 0), (2, 2)));
+        assert!(inventory.item_at((1, 1)).is_none());
+        assert_eq!(inventory.item_at((3, 3)).unwrap().0, (2, 2));
+        assert!(inventory.remove_item((2, 2)).is_some());
+        assert!(inventory.item_at((3, 3)).is_none());
+        assert!((0..4).all(|y| (0..4).all(|x| inventory.is_cell_free((x, y)))));
+
+        assert!(inventory.insert_item((0, 0), gem.item_new()).is_none());
+        assert!(inventory.insert_item((1, 1), gem.item_new()).is_none());
+        inventory
+            .get_item_mut((0, 0))
+            .unwrap()
+            .add_data(Footprint::rotatable(3, 1));
+        assert!(inventory.rotate_item((0, 0)));
+        assert!(inventory.is_cell_free((0, 1)));
+        assert!(inventory.remove_item((0, 0)).is_some());
+        assert!(inventory.item_at((1, 1)).is_some());
+        assert!(inventory.is_cell_free((0, 0)));
+    }
+
+    #[test]
+    fn place_and_remove_items() {
+        let crate_type = Arc::new(ItemType::with_data(Footprint::new(2, 2)));
+        let gem = Arc::new(ItemType::new());
+        let mut inventory = GridInventory::new(4, 3);
+
+        assert!(inventory
+            .insert_item((1, 1), crate_type.item_new())
+            .is_none());
+        assert!(inventory
+            .insert_item((0, 0), crate_type.item_new())
+            .is_some());
+        assert!(inventory
+            .insert_item((3, 2), crate_type.item_new())
+            .is_some());
+        assert!(inventory.insert_item((0, 2), gem.item_new()).is_none());
+        assert_eq!(inventory.len(), 2);
+
+        for cell in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            assert!(!inventory.is_cell_free(cell));
+            assert_eq!(inventory.item_at(cell).unwrap().0, (1, 1));
+        }
+        assert!(inventory.is_cell_free((3, 1)));
+        assert!(inventory.get_item((2, 2)).is_none());
+
+        assert!(inventory.remove_item((1, 1)).is_some());
+        assert!(inventory.is_cell_free((2, 2)));
+        assert!(inventory
+            .insert_item((0, 0), crate_type.item_new())
+            .is_none());
+    }
+
+    #[test]
+    fn rotate_items() {
+        let rifle = Arc::new(ItemType::with_data(Footprint::rotatable(3, 1)));
+        let brick = Arc::new(ItemType::with_data(Footprint::new(2, 1)));
+        let mut inventory = GridInventory::new(3, 3);
+
+        assert!(matches!(
+            inventory.try_insert_item((0, 0), brick.item_new(), true),
+            Err(ShrubError::NotAllowed(_))
+        ));
+        inventory
+            .try_insert_item((0, 0), rifle.item_new(), false)
+            .unwrap();
+        assert!(inventory.rotate_item((0, 0)));
+        assert_eq!(inventory.is_rotated((0, 0)), Some(true));
+        assert_eq!(inventory.item_at((0, 2)).unwrap().0, (0, 0));
+        assert!(inventory.is_cell_free((1, 0)));
+
+        inventory.insert_item((1, 0), brick.item_new());
+        assert!(!inventory.rotate_item((0, 0)));
+        assert!(!inventory.rotate_item((1, 0)));
+        assert_eq!(inventory.is_rotated((0, 0)), Some(true));
+    }
+
+    #[test]
+    fn move_items() {
+        let rifle = Arc::new(ItemType::with_data(Footprint::rotatable(3, 1)));
+        let mut inventory = GridInventory::new(3, 3);
+        inventory
+            .try_insert_item((0, 0), rifle.item_new(), true)
+            .unwrap();
+
+        assert!(inventory.move_item((0, 0), (0, 0)));
+        assert!(!inventory.move_item((0, 0), (0, 1)));
+        assert!(inventory.move_item((0, 0), (2, 0)));
+        assert_eq!(inventory.is_rotated((2, 0)), Some(true));
+        assert!(inventory.is_cell_free((0, 0)));
+        assert_eq!(inventory.item_at((2, 2)).unwrap().0, (2, 0));
+        assert!(!inventory.move_item((0, 0), (1, 0)));
+    }
+
+    #[test]
+    fn add_item_first_fit() {
+        let crate_type = Arc::new(ItemType::with_data(Footprint::new(2, 2)));
+        let rifle = Arc::new(ItemType::with_data(Footprint::rotatable(3, 1)));
+        let ammo = Arc::new(ItemType::with_data(MaxStackSize(30)));
+        let mut inventory = GridInventory::new(3, 3);
+
+        assert!(inventory.add_item(crate_type.item_new()).is_none());
+        // the first free cell in reading order is (2, 0), where the rifle only fits rotated
+        assert!(inventory.add_item(rifle.item_new()).is_none());
+        assert_eq!(inventory.is_rotated((2, 0)), Some(true));
+        assert_eq!(inventory.item_at((2, 2)).unwrap().0, (2, 0));
+
+        let mut rounds = ammo.item_new();
+        rounds.set_count(20);
+        assert!(inventory.add_item(rounds).is_none());
+        assert!(inventory.get_item((0, 2)).is_some());
+        let mut rounds = ammo.item_new();
+        rounds.set_count(20);
+        assert!(inventory.add_item(rounds).is_none());
+        assert_eq!(inventory.get_item((0, 2)).unwrap().count(), 30);
+        assert_eq!(inventory.get_item((1, 2)).unwrap().count(), 10);
+
+        assert!(inventory.add_item(crate_type.item_new()).is_some());
+    }
+
+    #[test]
+    fn transfer_and_transactions() {
+        let rifle = Arc::new(ItemType::with_data(Footprint::rotatable(3, 1)));
+        let mut inventory = GridInventory::new(2, 3);
+        inventory
+            .try_insert_item((1, 0), rifle.item_new(), true)
+            .unwrap();
+        let mut chest = SlotInventory::new(1);
+        chest.add_item(rifle.item_new());
+
+        let mut transaction = Transaction::new();
+        let grid = transaction.inventory(&mut inventory);
+        let slots = transaction.inventory(&mut chest);
+        transaction
+            .remove_item(grid, (1, 0))
+            .move_item(slots, 0, grid)
+            .move_item(slots, 0, grid);
+        assert!(transaction.commit().is_err());
+        assert_eq!(inventory.is_rotated((1, 0)), Some(true));
+        assert_eq!(inventory.len(), 1);
+        assert_eq!(chest.len(), 1);
+
+        assert_eq!(transfer(&mut chest, 0, &mut inventory).unwrap(), 1);
+        assert_eq!(inventory.is_rotated((0, 0)), Some(true));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn grid_json_roundtrip() {
+        let mut registry = crate::ItemRegistry::new();
+        let rifle = registry.register(
+            "test:rifle",
+            ItemType::with_data(Footprint::rotatable(3, 1)),
+        );
+        let gem = registry.register("test:gem", ItemType::new());
+        let mut inventory = GridInventory::new(3, 3);
+        inventory
+            .try_insert_item((2, 0), registry[rifle].item_new(), true)
+            .unwrap();
+        inventory.insert_item((0, 1), registry[gem].item_new());
+
+        let json = registry
+            .serde_scope(|| serde_json::to_string(&inventory))
+            .unwrap();
+        assert_eq!(
+            json,
+            r#"{"width":3,"height":3,"items":[{"x":2,"y":0,"rotated":true,"item":{"type":"test:rifle","count":1,"data":{}}},{"x":0,"y":1,"rotated":false,"item":{"type":"test:gem","count":1,"data":{}}}]}"#
+        );
+        let inventory: GridInventory = registry
+            .serde_scope(|| serde_json::from_str(&json))
+            .unwrap();
+        assert_eq!(inventory.item_at((2, 2)).unwrap().0, (2, 0));
+        assert_eq!(
+            inventory.get_item((0, 1)).unwrap().item_type_id(),
+            Some(gem)
+        );
+
+        let overlapping = json.replace(r#""x":0,"y":1"#, r#""x":2,"y":1"#);
+        assert!(registry
+            .serde_scope(|| serde_json::from_str::<GridInventory>(&overlapping))
+            .is_err());
+    }
+}
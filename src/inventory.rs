@@ -1,64 +1,74 @@
-use crate::Item;
+use crate::{Item, ItemDataQuery, ItemFilter, ShrubError};
 
 /// Trait to mark struct as inventory and provide basic function api
-pub trait Inventory<'a> {
+pub trait Inventory {
     /// Adds an item to the inventory.
     /// * when the inventory for some reason can't pickup the item, it will return the item in `Some(Item)`
     ///
     /// # Examples
     /// ```
-    /// use std::vec;
+    /// use std::{sync::Arc, vec};
     /// use shrub::{Inventory, InventorySelector, Item, ItemData, ItemType};
     ///
-    /// struct SimpleInventory<'a> {
-    ///     items: Vec<Item<'a>>,
+    /// struct SimpleInventory {
+    ///     items: Vec<Item>,
     /// }
-    /// impl<'a> Inventory<'a> for SimpleInventory<'a> {
-    ///     fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+    /// impl Inventory for SimpleInventory {
+    ///     fn add_item(&mut self, item: Item) -> Option<Item> {
     ///         self.items.push(item);
     ///         None
     ///     }
     /// }
     ///
     /// let mut inventory = SimpleInventory { items: vec![] };
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let item = item_type.item_new();
     /// if let Some(i) = inventory.add_item(item) {
     ///     println!("Couldn't add item to inventory");
     /// }
     /// ```
-    fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>>;
+    fn add_item(&mut self, item: Item) -> Option<Item>;
+
+    /// Like `add_item`, but tells why the item couldn't be added.
+    /// By default every rejected item is reported as `ShrubError::InventoryFull`,
+    /// inventories that know a more precise reason should override this.
+    fn try_add_item(&mut self, item: Item) -> Result<(), ShrubError> {
+        match self.add_item(item) {
+            Some(item) => Err(ShrubError::InventoryFull(item)),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Trait to implement selecting an item from an inventory
-pub trait InventorySelector<'a, S> {
+pub trait InventorySelector<S> {
     /// Borrows an item with the specified selector
     ///
     /// # Examples
     /// ```
-    /// # use std::vec;
+    /// # use std::{sync::Arc, vec};
     /// use shrub::{Inventory, InventorySelector, Item, ItemData, ItemType};
     ///
-    /// struct SimpleInventory<'a> {
-    ///     items: Vec<Item<'a>>,
+    /// struct SimpleInventory {
+    ///     items: Vec<Item>,
     /// }
-    /// # impl<'a> Inventory<'a> for SimpleInventory<'a> {
-    /// #     fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+    /// # impl Inventory for SimpleInventory {
+    /// #     fn add_item(&mut self, item: Item) -> Option<Item> {
     /// #         self.items.push(item);
     /// #         None
     /// #     }
     /// # }
-    /// impl<'a> InventorySelector<'a, usize> for SimpleInventory<'a> {
-    ///     fn get_item(&self, selector: usize) -> Option<&'a Item> {
+    /// impl InventorySelector<usize> for SimpleInventory {
+    ///     fn get_item(&self, selector: usize) -> Option<&Item> {
     ///         self.items.get(selector)
     ///     }
     ///     
     ///     // ...
-    ///     # fn get_item_mut(&mut self, selector: usize) -> Option<&'a mut Item> {
+    ///     # fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
     ///     #     self.items.get_mut(selector)
     ///     # }
     ///     #
-    ///     # fn remove_item(&mut self, selector: usize) -> Option<Item<'a>> {
+    ///     # fn remove_item(&mut self, selector: usize) -> Option<Item> {
     ///     #     if selector < self.items.len() {
     ///     #         Some(self.items.remove(selector))
     ///     #     } else {
@@ -68,41 +78,41 @@ pub trait InventorySelector<'a, S> {
     /// }
     ///
     /// let mut inventory = SimpleInventory { items: vec![] };
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let item = item_type.item_new();
     /// inventory.add_item(item);
     ///
     /// let item = inventory.get_item(0);
     /// ```
-    fn get_item(&self, selector: S) -> Option<&'a Item>;
+    fn get_item(&self, selector: S) -> Option<&Item>;
 
     /// Borrows an item as mutable with the specified selector
     ///
     /// # Examples
     /// ```
-    /// # use std::vec;
+    /// # use std::{sync::Arc, vec};
     /// use shrub::{Inventory, InventorySelector, Item, ItemData, ItemType};
     ///
-    /// struct SimpleInventory<'a> {
-    ///     items: Vec<Item<'a>>,
+    /// struct SimpleInventory {
+    ///     items: Vec<Item>,
     /// }
-    /// # impl<'a> Inventory<'a> for SimpleInventory<'a> {
-    /// #     fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+    /// # impl Inventory for SimpleInventory {
+    /// #     fn add_item(&mut self, item: Item) -> Option<Item> {
     /// #         self.items.push(item);
     /// #         None
     /// #     }
     /// # }
-    /// impl<'a> InventorySelector<'a, usize> for SimpleInventory<'a> {
-    ///     fn get_item_mut(&mut self, selector: usize) -> Option<&'a mut Item> {
+    /// impl InventorySelector<usize> for SimpleInventory {
+    ///     fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
     ///         self.items.get_mut(selector)
     ///     }
     ///     
     ///     // ...
-    ///     # fn get_item(&self, selector: usize) -> Option<&'a Item> {
+    ///     # fn get_item(&self, selector: usize) -> Option<&Item> {
     ///     #     self.items.get(selector)
     ///     # }
     ///     #
-    ///     # fn remove_item(&mut self, selector: usize) -> Option<Item<'a>> {
+    ///     # fn remove_item(&mut self, selector: usize) -> Option<Item> {
     ///     #     if selector < self.items.len() {
     ///     #         Some(self.items.remove(selector))
     ///     #     } else {
@@ -112,32 +122,32 @@ pub trait InventorySelector<'a, S> {
     /// }
     ///
     /// let mut inventory = SimpleInventory { items: vec![] };
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let item = item_type.item_new();
     /// inventory.add_item(item);
     ///
     /// let mut item = inventory.remove_item(0);
     /// ```
-    fn get_item_mut(&mut self, selector: S) -> Option<&'a mut Item>;
+    fn get_item_mut(&mut self, selector: S) -> Option<&mut Item>;
 
     /// Removes an item with the specified selector
     ///
     /// # Examples
     /// ```
-    /// # use std::vec;
+    /// # use std::{sync::Arc, vec};
     /// use shrub::{Inventory, InventorySelector, Item, ItemData, ItemType};
     ///
-    /// struct SimpleInventory<'a> {
-    ///     items: Vec<Item<'a>>,
+    /// struct SimpleInventory {
+    ///     items: Vec<Item>,
     /// }
-    /// # impl<'a> Inventory<'a> for SimpleInventory<'a> {
-    /// #     fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+    /// # impl Inventory for SimpleInventory {
+    /// #     fn add_item(&mut self, item: Item) -> Option<Item> {
     /// #         self.items.push(item);
     /// #         None
     /// #     }
     /// # }
-    /// impl<'a> InventorySelector<'a, usize> for SimpleInventory<'a> {
-    ///     fn remove_item(&mut self, selector: usize) -> Option<Item<'a>> {
+    /// impl InventorySelector<usize> for SimpleInventory {
+    ///     fn remove_item(&mut self, selector: usize) -> Option<Item> {
     ///         if selector < self.items.len() {
     ///             Some(self.items.remove(selector))
     ///         } else {
@@ -146,49 +156,201 @@ pub trait InventorySelector<'a, S> {
     ///     }
     ///
     ///     // ...
-    ///     # fn get_item_mut(&mut self, selector: usize) -> Option<&'a mut Item> {
+    ///     # fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
     ///     #     self.items.get_mut(selector)
     ///     # }
-    ///     # fn get_item(&self, selector: usize) -> Option<&'a Item> {
+    ///     # fn get_item(&self, selector: usize) -> Option<&Item> {
     ///     #     self.items.get(selector)
     ///     # }
     /// }
     ///
     /// let mut inventory = SimpleInventory { items: vec![] };
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let item = item_type.item_new();
     /// inventory.add_item(item);
     ///
     /// let mut item = inventory.get_item_mut(0);
     /// ```
-    fn remove_item(&mut self, selector: S) -> Option<Item<'a>>;
+    fn remove_item(&mut self, selector: S) -> Option<Item>;
+
+    /// Like `remove_item`, but fails with `ShrubError::SlotEmpty` when there is no item at the selector.
+    fn try_remove_item(&mut self, selector: S) -> Result<Item, ShrubError> {
+        self.remove_item(selector).ok_or(ShrubError::SlotEmpty)
+    }
+}
+
+/// Trait to iterate over all items of an inventory, regardless of where they are
+pub trait InventoryItems {
+    /// Iterates over all items of the inventory.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, InventoryItems, ItemType, MaxStackSize, SlotInventory};
+    ///
+    /// let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+    /// let mut inventory = SlotInventory::new(4);
+    /// let mut arrows = arrow.item_new();
+    /// arrows.set_count(40);
+    /// inventory.insert_item(1, arrows);
+    ///
+    /// let total: u32 = inventory.items().map(|item| item.count()).sum();
+    /// assert_eq!(total, 40);
+    /// ```
+    fn items(&self) -> impl Iterator<Item = &Item>;
+
+    /// Iterates mutably over all items of the inventory.
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item>;
+
+    /// Iterates over the data of all items that carry every data type of the query, on the item or its type.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, InventoryItems, ItemData, ItemType, SlotInventory};
+    ///
+    /// struct Damage(u32);
+    /// impl ItemData for Damage {}
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    ///
+    /// let sword = Arc::new(ItemType::with_data(Damage(7)));
+    /// let mut inventory = SlotInventory::new(4);
+    /// inventory.add_item(sword.item_with_data(Durability(100)));
+    /// inventory.add_item(sword.item_new());
+    ///
+    /// let damage: u32 = inventory.query::<Damage>().map(|damage| damage.0).sum();
+    /// assert_eq!(damage, 14);
+    ///
+    /// for (damage, durability) in inventory.query::<(Damage, Durability)>() {
+    ///     assert_eq!((damage.0, durability.0), (7, 100));
+    /// }
+    /// ```
+    fn query<Q: ItemDataQuery>(&self) -> impl Iterator<Item = Q::Ref<'_>> {
+        self.items().filter_map(Q::fetch)
+    }
+
+    /// Iterates mutably over the data of all items that carry every data type of the query themselves.
+    /// Data that is only on the `ItemType` isn't found, like with `Item::get_data_mut`.
+    ///
+    /// # Panics
+    /// Panics when a data type appears more than once in the query.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, InventoryItems, ItemData, ItemType, SlotInventory};
+    ///
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    /// struct Enchanted;
+    /// impl ItemData for Enchanted {}
+    ///
+    /// let sword = Arc::new(ItemType::new());
+    /// let mut inventory = SlotInventory::new(4);
+    /// inventory.add_item(sword.item_with_data(Durability(100)));
+    /// inventory.add_item(sword.item_with_data((Durability(100), Enchanted)));
+    ///
+    /// // enchanted items don't wear off
+    /// for durability in inventory.query_mut::<Durability>() {
+    ///     durability.0 -= 1;
+    /// }
+    /// for (durability, _) in inventory.query_mut::<(Durability, Enchanted)>() {
+    ///     durability.0 += 1;
+    /// }
+    /// let durabilities: Vec<u32> = inventory.query::<Durability>().map(|d| d.0).collect();
+    /// assert_eq!(durabilities, vec![99, 100]);
+    /// ```
+    fn query_mut<Q: ItemDataQuery>(&mut self) -> impl Iterator<Item = Q::Mut<'_>> {
+        self.items_mut().filter_map(Q::fetch_mut)
+    }
+
+    /// Returns the total count of the items the filter accepts.
+    fn count_matching(&self, filter: &ItemFilter) -> u32 {
+        self.items()
+            .filter(|item| filter.matches(item))
+            .map(Item::count)
+            .sum()
+    }
+
+    /// Returns the total count of the items whose type has the tag, see `ItemType::has_tag`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, InventoryItems, ItemType, MaxStackSize, SlotInventory};
+    ///
+    /// let oak_log = Arc::new(ItemType::with_data(MaxStackSize(64)).with_tag("core:logs"));
+    /// let birch_log = Arc::new(ItemType::with_data(MaxStackSize(64)).with_tag("core:logs"));
+    /// let mut inventory = SlotInventory::new(4);
+    /// let mut logs = oak_log.item_new();
+    /// logs.set_count(3);
+    /// inventory.add_item(logs);
+    /// inventory.add_item(birch_log.item_new());
+    ///
+    /// assert_eq!(inventory.count_tagged("#core:logs"), 4);
+    /// ```
+    fn count_tagged(&self, tag: &str) -> u32 {
+        self.items()
+            .filter(|item| item.item_type().has_tag(tag))
+            .map(Item::count)
+            .sum()
+    }
+}
+
+/// Trait to iterate over all items of an inventory together with the selectors that select them
+pub trait InventoryEntries<S>: InventorySelector<S> {
+    /// Whether adding, inserting or removing an item leaves the selectors of all other items as they are,
+    /// like the slots of a `SlotInventory` do. Inventories which shift their items, like a `Vec` without gaps, don't.
+    ///
+    /// An `Observed` only compares the items at the selectors a call changed when the selectors are stable,
+    /// otherwise it compares all items.
+    const STABLE_SELECTORS: bool = false;
+
+    /// Iterates over all items of the inventory together with their selectors.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{InventoryEntries, ItemType, SlotInventory, TransactionInventory};
+    ///
+    /// let apple = Arc::new(ItemType::new());
+    /// let mut inventory = SlotInventory::new(4);
+    /// inventory.insert_item(2, apple.item_new());
+    ///
+    /// let slots: Vec<usize> = inventory.entries().map(|(slot, _)| slot).collect();
+    /// assert_eq!(slots, vec![2]);
+    /// ```
+    fn entries(&self) -> impl Iterator<Item = (S, &Item)>;
 }
 
 #[cfg(test)]
 mod tests {
-    use std::vec;
+    use std::{sync::Arc, vec};
 
-    use crate::{Inventory, InventorySelector, Item, ItemData, ItemType};
+    use crate::{
+        Inventory, InventoryItems, InventorySelector, Item, ItemData, ItemType, SlotInventory,
+    };
 
-    struct SimpleInventory<'a> {
-        pub items: Vec<Item<'a>>,
+    struct SimpleInventory {
+        pub items: Vec<Item>,
     }
-    impl<'a> Inventory<'a> for SimpleInventory<'a> {
-        fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+    impl Inventory for SimpleInventory {
+        fn add_item(&mut self, item: Item) -> Option<Item> {
             self.items.push(item);
             None
         }
     }
-    impl<'a> InventorySelector<'a, usize> for SimpleInventory<'a> {
-        fn get_item(&self, selector: usize) -> Option<&'a Item> {
+    impl InventorySelector<usize> for SimpleInventory {
+        fn get_item(&self, selector: usize) -> Option<&Item> {
             self.items.get(selector)
         }
 
-        fn get_item_mut(&mut self, selector: usize) -> Option<&'a mut Item> {
+        fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
             self.items.get_mut(selector)
         }
 
-        fn remove_item(&mut self, selector: usize) -> Option<Item<'a>> {
+        fn remove_item(&mut self, selector: usize) -> Option<Item> {
             if selector < self.items.len() {
                 Some(self.items.remove(selector))
             } else {
@@ -209,7 +371,7 @@ mod tests {
     #[test]
     fn create_basic_inventory() {
         let mut inventory = SimpleInventory { items: vec![] };
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         let item = item_type.item_new();
 
         assert_eq!(inventory.items.len(), 0);
@@ -219,13 +381,13 @@ mod tests {
 
     #[test]
     fn create_nested_inventory() {
-        struct NestedInventory<'a> {
-            pub weapons: SimpleInventory<'a>,
-            pub food: SimpleInventory<'a>,
-            pub blocks: SimpleInventory<'a>,
+        struct NestedInventory {
+            pub weapons: SimpleInventory,
+            pub food: SimpleInventory,
+            pub blocks: SimpleInventory,
         }
-        impl<'a> Inventory<'a> for NestedInventory<'a> {
-            fn add_item(&mut self, item: Item<'a>) -> Option<Item<'a>> {
+        impl Inventory for NestedInventory {
+            fn add_item(&mut self, item: Item) -> Option<Item> {
                 match item.get_data::<CategoryData>()?.0 {
                     Category::Weapon => self.weapons.add_item(item),
                     Category::Food => self.food.add_item(item),
@@ -243,7 +405,7 @@ mod tests {
         assert!(nested_inventory.food.items.is_empty());
         assert!(nested_inventory.blocks.items.is_empty());
 
-        let wooden_sword = ItemType::with_data(CategoryData(Category::Weapon));
+        let wooden_sword = Arc::new(ItemType::with_data(CategoryData(Category::Weapon)));
         let item = wooden_sword.item_new();
         nested_inventory.add_item(item);
 
@@ -266,7 +428,7 @@ mod tests {
     #[test]
     fn remove_item() {
         let mut inventory = SimpleInventory { items: vec![] };
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         let item = item_type.item_new();
 
         assert_eq!(inventory.items.len(), 0);
@@ -275,4 +437,37 @@ mod tests {
         assert!(inventory.remove_item(0).is_some());
         assert_eq!(inventory.items.len(), 0);
     }
+
+    #[test]
+    fn query_item_data() {
+        struct Damage(u32);
+        impl ItemData for Damage {}
+
+        let sword = Arc::new(ItemType::with_data((
+            CategoryData(Category::Weapon),
+            Damage(5),
+        )));
+        let apple = Arc::new(ItemType::with_data(CategoryData(Category::Food)));
+        let stone = Arc::new(ItemType::with_data(CategoryData(Category::Block)));
+        let mut inventory = SlotInventory::new(4);
+        inventory.add_item(sword.item_new());
+        inventory.add_item(apple.item_with_data(Damage(1)));
+        inventory.add_item(stone.item_new());
+        inventory.add_item(sword.item_with_data(Damage(8)));
+
+        let weapons: Vec<u32> = inventory
+            .query::<(CategoryData, Damage)>()
+            .filter(|(category, _)| category.0 == Category::Weapon)
+            .map(|(_, damage)| damage.0)
+            .collect();
+        assert_eq!(weapons, vec![5, 8]);
+
+        // only items with their own damage are found
+        for damage in inventory.query_mut::<Damage>() {
+            damage.0 *= 2;
+        }
+        let damage: Vec<u32> = inventory.query::<Damage>().map(|damage| damage.0).collect();
+        assert_eq!(damage, vec![5, 2, 16]);
+        assert_eq!(inventory.query_mut::<(Damage, CategoryData)>().count(), 0);
+    }
 }
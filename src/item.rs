@@ -1,40 +1,338 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{any::TypeId, collections::HashMap, marker::PhantomData, sync::Arc};
 
-use crate::{itemdata_reflection::ItemDataReflection, ItemData, ItemType};
+use crate::{
+    changes::ChangeTracker, itemdata_reflection::ItemDataReflection, Generation, InheritData,
+    ItemData, ItemDataQuery, ItemType, ItemTypeId, MaxStackSize, ShrubError,
+};
 
 pub(crate) type ItemDataMap = HashMap<TypeId, Box<dyn ItemData>>;
 
+/// Duplicates every data of the map, or returns `None` when any of it can't be duplicated.
+pub(crate) fn clone_data_map(data: &ItemDataMap) -> Option<ItemDataMap> {
+    data.iter()
+        .map(|(type_id, data)| Some((*type_id, data.clone_data()?)))
+        .collect()
+}
+
 /// An Item holds his `ItemType` together with custom `ItemData`s for this specific Item.
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Item<'t> {
-    pub item_type: &'t ItemType,
-    data: ItemDataMap,
+/// A single Item can represent a whole stack of items, see `Item::count`.
+pub struct Item {
+    item_type: Arc<ItemType>,
+    pub(crate) count: u32,
+    pub(crate) data: ItemDataMap,
+    /// The generations of the item and its data, when it tracks its changes, see `Item::track_changes`.
+    changes: Option<Box<ChangeTracker>>,
+}
+
+/// Prints the data of an `ItemDataMap` sorted by type name, so the output doesn't depend on the order of the map.
+pub(crate) struct DebugDataMap<'a>(pub(crate) &'a ItemDataMap);
+
+impl std::fmt::Debug for DebugDataMap<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut data: Vec<&dyn ItemData> = self.0.values().map(AsRef::as_ref).collect();
+        data.sort_by_key(|data| data.type_name());
+        f.debug_list().entries(data).finish()
+    }
+}
+
+/// Where the data of an `Item` comes from, see `Item::data_source`.
+#[derive(Debug, Clone)]
+pub enum DataSource {
+    /// The item carries the data itself.
+    Item,
+    /// The data comes from the `ItemType` of the item or one of its parents.
+    ItemType(Arc<ItemType>),
+}
+
+impl PartialEq for DataSource {
+    /// ItemTypes are compared by identity, so two sources are only equal when they are the same ItemType.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DataSource::Item, DataSource::Item) => true,
+            (DataSource::ItemType(a), DataSource::ItemType(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Borrowed data of an `Item` or `ItemType`, see `Item::get_data`.
+///
+/// Data of an `ItemType` is shared with its definition, so it stays readable when the type is replaced meanwhile,
+/// see `ItemRegistry::replace`. The replaced definition is dropped with the last `DataRef` reading from it.
+pub struct DataRef<'a, D: ItemData> {
+    data: DataRefSource<'a, D>,
+}
+
+enum DataRefSource<'a, D> {
+    Item(&'a D),
+    ItemType(Arc<ItemDataMap>, PhantomData<&'a D>),
+}
+
+impl<'a, D: ItemData> DataRef<'a, D> {
+    fn item(data: &'a D) -> Self {
+        DataRef {
+            data: DataRefSource::Item(data),
+        }
+    }
+
+    /// Borrows the data from the data map of an `ItemType`, or returns `None` when the map doesn't contain it.
+    pub(crate) fn shared(data: Arc<ItemDataMap>) -> Option<Self> {
+        data.get(&TypeId::of::<D>())?.downcast_ref::<D>()?;
+        Some(DataRef {
+            data: DataRefSource::ItemType(data, PhantomData),
+        })
+    }
+}
+
+impl<D: ItemData> std::ops::Deref for DataRef<'_, D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        match &self.data {
+            DataRefSource::Item(data) => data,
+            DataRefSource::ItemType(data, _) => data[&TypeId::of::<D>()]
+                .downcast_ref::<D>()
+                .expect("the data was checked when borrowing it"),
+        }
+    }
+}
+
+impl<D: ItemData> Clone for DataRef<'_, D> {
+    fn clone(&self) -> Self {
+        let data = match &self.data {
+            DataRefSource::Item(data) => DataRefSource::Item(*data),
+            DataRefSource::ItemType(data, _) => DataRefSource::ItemType(data.clone(), PhantomData),
+        };
+        DataRef { data }
+    }
+}
+
+impl<D: ItemData + std::fmt::Debug> std::fmt::Debug for DataRef<'_, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        D::fmt(self, f)
+    }
 }
 
-impl<'t> Item<'t> {
+impl std::fmt::Debug for Item {
+    /// Prints the key of the `ItemType`, the count and the data of the item itself, see `ItemData::as_debug`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Item")
+            .field("item_type", &self.item_type().key())
+            .field("count", &self.count)
+            .field("data", &DebugDataMap(&self.data))
+            .finish()
+    }
+}
+
+impl Item {
     /// Creates a new Item, the HashMap for the data will not allocate until it is first inserted into.
-    pub(crate) fn new(item_type: &'t ItemType) -> Self {
+    pub(crate) fn new(item_type: Arc<ItemType>) -> Self {
         let data = HashMap::new();
-        Item { item_type, data }
+        Item {
+            item_type,
+            count: 1,
+            data,
+            changes: None,
+        }
     }
 
     /// Creates a new Item with the given data for the item.
-    pub(crate) fn with_data<D: ItemDataReflection>(item_type: &'t ItemType, item_data: D) -> Self {
+    pub(crate) fn with_data<D: ItemDataReflection>(item_type: Arc<ItemType>, item_data: D) -> Self {
         let mut data = HashMap::with_capacity(D::CAPACITY);
         item_data.add_data(&mut data);
-        Item { item_type, data }
+        Item {
+            item_type,
+            count: 1,
+            data,
+            changes: None,
+        }
     }
 
     /// Creates a new Item and reserves the given amount of capacity for the data map.
-    pub(crate) fn with_capacity(item_type: &'t ItemType, data_capacity: usize) -> Self {
+    pub(crate) fn with_capacity(item_type: Arc<ItemType>, data_capacity: usize) -> Self {
         let data = HashMap::with_capacity(data_capacity);
-        Item { item_type, data }
+        Item {
+            item_type,
+            count: 1,
+            data,
+            changes: None,
+        }
+    }
+
+    /// Borrows the `ItemType` of this item.
+    #[inline]
+    pub fn item_type(&self) -> &Arc<ItemType> {
+        &self.item_type
+    }
+
+    /// Returns the id of the `ItemType` of this item, or `None` when the type isn't registered in an `ItemRegistry`.
+    #[inline]
+    pub fn item_type_id(&self) -> Option<ItemTypeId> {
+        self.item_type().id()
+    }
+
+    /// Returns how many items this stack represents. New items always start with a count of 1.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Sets how many items this stack represents.
+    /// The count isn't clamped to `max_stack_size`, so oversized stacks can be created on purpose.
+    #[inline]
+    pub fn set_count(&mut self, count: u32) {
+        if self.count != count {
+            self.count = count;
+            self.item_changed();
+        }
+    }
+
+    /// Returns the maximum amount of items a stack of this item can hold.
+    /// It is read from the `MaxStackSize` data of the item or its `ItemType` and defaults to 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemType, MaxStackSize};
+    ///
+    /// let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+    /// assert_eq!(arrow.item_new().max_stack_size(), 64);
+    ///
+    /// let sword = Arc::new(ItemType::new());
+    /// assert_eq!(sword.item_new().max_stack_size(), 1);
+    /// ```
+    #[inline]
+    pub fn max_stack_size(&self) -> u32 {
+        self.get_data::<MaxStackSize>().map_or(1, |max| max.0)
+    }
+
+    /// Returns `true` when both items could be part of the same stack.
+    /// That is the case when they share the same `ItemType` and their own data is equal according to `ItemData::stack_eq`.
+    /// Data of the `ItemType` isn't compared, as both items share it anyway.
+    pub fn can_stack_with(&self, other: &Item) -> bool {
+        self.item_type == other.item_type
+            && self.data.len() == other.data.len()
+            && self.data.iter().all(|(type_id, data)| {
+                other
+                    .data
+                    .get(type_id)
+                    .is_some_and(|other| data.stack_eq(other.as_ref()))
+            })
+    }
+
+    /// Moves as many items from `other` onto this stack as `max_stack_size` allows.
+    /// * when `other` can't be stacked onto this item or doesn't fit completely, the remainder is returned in `Some(Item)`
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemType, MaxStackSize};
+    ///
+    /// let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+    /// let mut stack = arrow.item_new();
+    /// stack.set_count(10);
+    /// let mut other = arrow.item_new();
+    /// other.set_count(10);
+    ///
+    /// let remainder = stack.merge(other).unwrap();
+    /// assert_eq!(stack.count(), 16);
+    /// assert_eq!(remainder.count(), 4);
+    /// ```
+    pub fn merge(&mut self, mut other: Item) -> Option<Item> {
+        if !self.can_stack_with(&other) {
+            return Some(other);
+        }
+        self.take_from(&mut other);
+        if other.count == 0 {
+            None
+        } else {
+            Some(other)
+        }
+    }
+
+    /// Like `merge`, but only moves the count and leaves `other` in place, even when it ends up empty.
+    /// Returns how many items were moved onto this stack.
+    pub fn take_from(&mut self, other: &mut Item) -> u32 {
+        if !self.can_stack_with(other) {
+            return 0;
+        }
+        let moved = self
+            .max_stack_size()
+            .saturating_sub(self.count)
+            .min(other.count);
+        if moved > 0 {
+            self.set_count(self.count + moved);
+            other.set_count(other.count - moved);
+        }
+        moved
+    }
+
+    /// Splits `count` items off this stack into a new stack of the same type and with a copy of the item's own data.
+    /// Returns `None` when `count` is 0 or not smaller than the stack, or when the item carries data of its own
+    /// which can't be duplicated, see `ItemData::clone_data`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemType, MaxStackSize};
+    ///
+    /// let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+    /// let mut stack = arrow.item_new();
+    /// stack.set_count(10);
+    ///
+    /// let half = stack.split(5).unwrap();
+    /// assert_eq!((stack.count(), half.count()), (5, 5));
+    /// assert!(stack.split(5).is_none());
+    /// ```
+    pub fn split(&mut self, count: u32) -> Option<Item> {
+        if count == 0 || count >= self.count {
+            return None;
+        }
+        let mut item = self.try_clone()?;
+        item.set_count(count);
+        self.set_count(self.count - count);
+        Some(item)
+    }
+
+    /// Duplicates the item together with its own data.
+    /// Returns `None` when any of its data can't be duplicated, see `ItemData::clone_data`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemData, ItemType};
+    ///
+    /// #[derive(Clone, Debug)]
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {
+    ///     fn clone_data(&self) -> Option<Box<dyn ItemData>> {
+    ///         Some(Box::new(self.clone()))
+    ///     }
+    ///
+    ///     fn as_debug(&self) -> Option<&dyn std::fmt::Debug> {
+    ///         Some(self)
+    ///     }
+    /// }
+    ///
+    /// let sword = Arc::new(ItemType::new()).item_with_data(Durability(100));
+    /// let copy = sword.try_clone().unwrap();
+    /// assert_eq!(copy.get_data::<Durability>().unwrap().0, 100);
+    /// assert_eq!(format!("{copy:?}"), "Item { item_type: None, count: 1, data: [Durability(100)] }");
+    /// ```
+    pub fn try_clone(&self) -> Option<Item> {
+        Some(Item {
+            item_type: self.item_type().clone(),
+            count: self.count,
+            data: clone_data_map(&self.data)?,
+            changes: self.changes.clone(),
+        })
     }
 
     /// Adds the given data to the item. When data with the given datatype already exists, it will be completly overriden.
     ///
     /// # Examples
     /// ```
+    /// use std::sync::Arc;
     /// use shrub::{ItemData, ItemType};
     ///
     /// struct CoolData {
@@ -42,7 +340,7 @@ impl<'t> Item<'t> {
     /// }
     /// impl ItemData for CoolData {}
     ///
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let mut item = item_type.item_with_capacity(1);
     /// item.add_data(CoolData { is_cool: true });
     /// assert_eq!(item.get_data::<CoolData>().unwrap().is_cool, true);
@@ -51,12 +349,17 @@ impl<'t> Item<'t> {
     pub fn add_data<D: ItemDataReflection>(&mut self, item_data: D) {
         self.data.reserve(D::CAPACITY);
         item_data.add_data(&mut self.data);
+        if let Some(changes) = &mut self.changes {
+            D::for_each_type_id(|type_id| changes.data_changed(type_id));
+        }
     }
 
-    /// Borrows data of the given datatype from the item. When the item doesn't have data from this datatype, it searches in the `ItemType`.
+    /// Borrows data of the given datatype from the item. When the item doesn't have data from this datatype, it searches in the `ItemType`
+    /// and its parents.
     ///
     /// # Examples
     /// ```
+    /// use std::sync::Arc;
     /// use shrub::{ItemData, ItemType};
     ///
     /// struct CoolData {
@@ -64,15 +367,15 @@ impl<'t> Item<'t> {
     /// }
     /// impl ItemData for CoolData {}
     ///
-    /// let item_type = ItemType::with_data(CoolData { is_cool: true });
+    /// let item_type = Arc::new(ItemType::with_data(CoolData { is_cool: true }));
     /// let item = item_type.item_new();
     /// assert_eq!(item.get_data::<CoolData>().unwrap().is_cool, true); // this first searches in the item, then in the itemtype
     /// ```
     #[inline]
-    pub fn get_data<D: ItemData>(&self) -> Option<&D> {
+    pub fn get_data<D: ItemData>(&self) -> Option<DataRef<'_, D>> {
         match self.data.get(&TypeId::of::<D>()) {
-            Some(d) => Some(d.as_any().downcast_ref::<D>().unwrap()),
-            None => self.item_type.get_data::<D>(),
+            Some(d) => d.downcast_ref::<D>().map(DataRef::item),
+            None => self.item_type().get_data::<D>(),
         }
     }
 
@@ -83,6 +386,7 @@ impl<'t> Item<'t> {
     ///
     /// # Examples
     /// ```
+    /// use std::sync::Arc;
     /// use shrub::{ItemData, ItemType};
     ///
     /// struct CoolData {
@@ -90,7 +394,7 @@ impl<'t> Item<'t> {
     /// }
     /// impl ItemData for CoolData {}
     ///
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// let mut item = item_type.item_with_data(CoolData { is_cool: true });
     /// assert_eq!(item.get_data::<CoolData>().unwrap().is_cool, true);
     /// item.get_data_mut::<CoolData>().unwrap().is_cool = false;
@@ -98,19 +402,59 @@ impl<'t> Item<'t> {
     /// ```
     #[inline]
     pub fn get_data_mut<D: ItemData>(&mut self) -> Option<&mut D> {
-        match self.data.get_mut(&TypeId::of::<D>()) {
-            Some(d) => Some(d.as_any_mut().downcast_mut::<D>().unwrap()),
-            None => None,
+        let type_id = TypeId::of::<D>();
+        if let Some(changes) = &mut self.changes {
+            if self.data.contains_key(&type_id) {
+                changes.data_changed(type_id);
+            }
+        }
+        self.data.get_mut(&type_id)?.downcast_mut::<D>()
+    }
+
+    /// Borrows data of the given datatype from the item as mutable.
+    /// When the item doesn't have data from this datatype, it copies the data of the `ItemType` into the item first,
+    /// so changes only affect this item. Returns `None` when the `ItemType` doesn't have the data either.
+    ///
+    /// The item keeps its own copy from then on, so it only stacks with items whose copy is equal according to
+    /// `ItemData::stack_eq`. Removing the copy with `remove_data` makes the item fall back to the `ItemType` again.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{InheritData, ItemData, ItemType};
+    ///
+    /// #[derive(Clone)]
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    /// impl InheritData for Durability {}
+    ///
+    /// let sword = Arc::new(ItemType::with_data(Durability(100)));
+    /// let mut item = sword.item_new();
+    /// assert!(item.get_data_mut::<Durability>().is_none());
+    /// item.get_data_mut_or_inherit::<Durability>().unwrap().0 -= 1;
+    /// assert_eq!(item.get_data::<Durability>().unwrap().0, 99);
+    /// assert_eq!(sword.get_data::<Durability>().unwrap().0, 100);
+    /// ```
+    pub fn get_data_mut_or_inherit<D: InheritData>(&mut self) -> Option<&mut D> {
+        let type_id = TypeId::of::<D>();
+        if !self.data.contains_key(&type_id) {
+            let inherited = self
+                .item_type()
+                .get_data::<D>()
+                .map(|data| D::clone(&data))?;
+            self.data.insert(type_id, Box::new(inherited));
         }
+        self.get_data_mut::<D>()
     }
 
     /// Remove data of passed type from item
     ///
     /// # Examples
     /// ```
+    /// use std::sync::Arc;
     /// use shrub::{ItemData, ItemType};
     ///
-    /// let item_type = ItemType::new();
+    /// let item_type = Arc::new(ItemType::new());
     /// struct SimpleData(bool);
     /// impl ItemData for SimpleData {}
     /// let mut item = item_type.item_with_data(SimpleData(true));
@@ -119,34 +463,240 @@ impl<'t> Item<'t> {
     /// assert!(item.get_data::<SimpleData>().is_none());
     /// ```
     pub fn remove_data<D: ItemData>(&mut self) -> Option<Box<D>> {
-        match self.data.remove(&TypeId::of::<D>()) {
-            Some(d) => Some(d.downcast::<D>().ok().unwrap()),
-            None => None,
+        let data = self.data.remove(&TypeId::of::<D>())?;
+        if let Some(changes) = &mut self.changes {
+            changes.data_changed(TypeId::of::<D>());
         }
+        data.downcast::<D>().ok()
+    }
+
+    /// Tells whether the item itself or which `ItemType` along the parents of its type supplies the data of the given datatype.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{DataSource, ItemData, ItemType};
+    ///
+    /// struct Damage(u32);
+    /// impl ItemData for Damage {}
+    ///
+    /// let weapon = Arc::new(ItemType::with_data(Damage(1)));
+    /// let sword = Arc::new(ItemType::new().with_parent(weapon.clone()));
+    ///
+    /// match sword.item_new().data_source::<Damage>() {
+    ///     Some(DataSource::ItemType(item_type)) => assert!(Arc::ptr_eq(&item_type, &weapon)),
+    ///     _ => unreachable!(),
+    /// }
+    /// assert_eq!(sword.item_with_data(Damage(3)).data_source::<Damage>(), Some(DataSource::Item));
+    /// ```
+    pub fn data_source<D: ItemData>(&self) -> Option<DataSource> {
+        if self.data.contains_key(&TypeId::of::<D>()) {
+            return Some(DataSource::Item);
+        }
+        self.item_type()
+            .data_source::<D>()
+            .map(DataSource::ItemType)
+    }
+
+    /// Borrows data of several datatypes at once, each searched in the item first and then in the `ItemType` like `get_data`.
+    /// Returns `None` when any of the data is missing.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemData, ItemType};
+    ///
+    /// struct Damage(u32);
+    /// impl ItemData for Damage {}
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    ///
+    /// let sword = Arc::new(ItemType::with_data(Damage(7)));
+    /// let item = sword.item_with_data(Durability(100));
+    /// let (damage, durability) = item.get_many::<(Damage, Durability)>().unwrap();
+    /// assert_eq!((damage.0, durability.0), (7, 100));
+    /// ```
+    #[inline]
+    pub fn get_many<Q: ItemDataQuery>(&self) -> Option<Q::Ref<'_>> {
+        Q::fetch(self)
+    }
+
+    /// Borrows data of several datatypes of the item at once as mutable.
+    /// Like `get_data_mut`, it WON'T SEARCH IN THE ITEMTYPE and returns `None` when any of the data is missing on the item.
+    ///
+    /// # Panics
+    /// Panics when a datatype appears more than once in the tuple, as it can't be borrowed mutably twice.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemData, ItemType};
+    ///
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    /// struct Sharpness(u32);
+    /// impl ItemData for Sharpness {}
+    ///
+    /// let mut item = Arc::new(ItemType::new()).item_with_data((Durability(100), Sharpness(3)));
+    /// let (durability, sharpness) = item.get_many_mut::<(Durability, Sharpness)>().unwrap();
+    /// durability.0 -= sharpness.0;
+    /// sharpness.0 -= 1;
+    /// assert_eq!(item.get_data::<Durability>().unwrap().0, 97);
+    /// ```
+    #[inline]
+    pub fn get_many_mut<Q: ItemDataQuery>(&mut self) -> Option<Q::Mut<'_>> {
+        Q::fetch_mut(self)
+    }
+
+    /// Starts tracking changes to the item and its data with generations, see `Generation`.
+    /// Tracking is opt-in, as it costs an allocation per item and a little time on every change.
+    ///
+    /// A tracked item counts as changed when its count changes or data is added, removed or borrowed as mutable.
+    /// Data borrowed as mutable counts as changed even when it is left as it was.
+    /// Copies made with `try_clone` or `split` keep tracking, but saved and loaded items don't.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Generation, ItemData, ItemType};
+    ///
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    /// struct Enchanted;
+    /// impl ItemData for Enchanted {}
+    ///
+    /// let mut sword = Arc::new(ItemType::new()).item_with_data(Durability(100));
+    /// sword.track_changes();
+    /// let rendered = Generation::current();
+    ///
+    /// sword.get_data_mut::<Durability>().unwrap().0 -= 1;
+    /// assert!(sword.changed_since(rendered));
+    /// assert!(sword.data_changed_since::<Durability>(rendered));
+    /// assert!(!sword.data_changed_since::<Enchanted>(rendered));
+    /// ```
+    pub fn track_changes(&mut self) {
+        if self.changes.is_none() {
+            self.changes = Some(Box::new(ChangeTracker::new(self.data.keys().copied())));
+        }
+    }
+
+    /// Returns `true` when the item tracks its changes, see `Item::track_changes`.
+    #[inline]
+    pub fn is_tracking_changes(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    /// Returns the generation of the latest change to the item, or `None` when it doesn't track its changes.
+    #[inline]
+    pub fn generation(&self) -> Option<Generation> {
+        self.changes.as_ref().map(|changes| changes.item)
+    }
+
+    /// Returns the generation of the latest change to the data of the given datatype on the item itself.
+    /// Returns `None` when the item doesn't track its changes or never carried the data while tracking them.
+    #[inline]
+    pub fn data_generation<D: ItemData>(&self) -> Option<Generation> {
+        self.changes.as_ref()?.data.get(&TypeId::of::<D>()).copied()
+    }
+
+    /// Returns `true` when the item changed after the given generation.
+    /// Items that don't track their changes always count as changed, as nothing tells otherwise.
+    #[inline]
+    pub fn changed_since(&self, generation: Generation) -> bool {
+        self.generation().is_none_or(|changed| changed > generation)
+    }
+
+    /// Returns `true` when the data of the given datatype on the item itself changed after the given generation.
+    /// Items that don't track their changes always count as changed, as nothing tells otherwise.
+    #[inline]
+    pub fn data_changed_since<D: ItemData>(&self, generation: Generation) -> bool {
+        match &self.changes {
+            Some(changes) => changes
+                .data
+                .get(&TypeId::of::<D>())
+                .is_some_and(|changed| *changed > generation),
+            None => true,
+        }
+    }
+
+    /// Returns the generation of the latest change to any data on the item itself, including data that was removed since.
+    pub(crate) fn latest_data_generation(&self) -> Option<Generation> {
+        self.changes.as_ref()?.data.values().max().copied()
+    }
+
+    /// Marks the item as changed when it tracks its changes.
+    #[inline]
+    fn item_changed(&mut self) {
+        if let Some(changes) = &mut self.changes {
+            changes.item_changed();
+        }
+    }
+
+    /// Splits the borrow of the item into its data and its change tracker, for borrowing several data mutably at once.
+    pub(crate) fn data_and_changes(&mut self) -> (&mut ItemDataMap, Option<&mut ChangeTracker>) {
+        (&mut self.data, self.changes.as_deref_mut())
+    }
+
+    /// Like `get_data`, but fails with `ShrubError::DataMissing` naming the datatype.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemData, ItemType, ShrubError};
+    ///
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    ///
+    /// let item = Arc::new(ItemType::new()).item_new();
+    /// match item.try_get_data::<Durability>() {
+    ///     Err(ShrubError::DataMissing { type_name }) => assert!(type_name.ends_with("Durability")),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    #[inline]
+    pub fn try_get_data<D: ItemData>(&self) -> Result<DataRef<'_, D>, ShrubError> {
+        self.get_data::<D>()
+            .ok_or_else(ShrubError::data_missing::<D>)
+    }
+
+    /// Like `get_data_mut`, but fails with `ShrubError::DataMissing` naming the datatype.
+    #[inline]
+    pub fn try_get_data_mut<D: ItemData>(&mut self) -> Result<&mut D, ShrubError> {
+        self.get_data_mut::<D>()
+            .ok_or_else(ShrubError::data_missing::<D>)
+    }
+
+    /// Like `remove_data`, but fails with `ShrubError::DataMissing` naming the datatype.
+    #[inline]
+    pub fn try_remove_data<D: ItemData>(&mut self) -> Result<Box<D>, ShrubError> {
+        self.remove_data::<D>()
+            .ok_or_else(ShrubError::data_missing::<D>)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Item, ItemData, ItemType};
+    use std::sync::Arc;
+
+    use crate::{Generation, InheritData, Item, ItemData, ItemRegistry, ItemType, MaxStackSize};
 
     #[test]
     fn create_item_with_capacity() {
-        let item_type = ItemType::new();
-        let item = Item::with_capacity(&item_type, 0);
-        assert_eq!(*item.item_type, item_type);
+        let item_type = Arc::new(ItemType::new());
+        let item = Item::with_capacity(item_type.clone(), 0);
+        assert_eq!(item.item_type(), &item_type);
     }
 
     #[test]
     fn create_item_with_single_data() {
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         struct SaturationData {
             saturation: i16,
         }
         impl ItemData for SaturationData {}
         let saturation = 7;
-        let item = Item::with_data(&item_type, SaturationData { saturation });
-        assert_eq!(*item.item_type, item_type);
+        let item = Item::with_data(item_type.clone(), SaturationData { saturation });
+        assert_eq!(item.item_type(), &item_type);
         assert_eq!(
             item.get_data::<SaturationData>().unwrap().saturation,
             saturation
@@ -155,7 +705,7 @@ mod tests {
 
     #[test]
     fn create_item_with_multiple_data() {
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         struct SaturationData {
             saturation: i16,
         }
@@ -167,10 +717,10 @@ mod tests {
         let saturation = 7;
         let speed = 17.3;
         let item = Item::with_data(
-            &item_type,
+            item_type.clone(),
             (SaturationData { saturation }, SpeedData { speed }),
         );
-        assert_eq!(*item.item_type, item_type);
+        assert_eq!(item.item_type(), &item_type);
         assert_eq!(
             item.get_data::<SaturationData>().unwrap().saturation,
             saturation
@@ -180,14 +730,14 @@ mod tests {
 
     #[test]
     fn create_item_with_single_mutable_data() {
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         struct SaturationData {
             saturation: i16,
         }
         impl ItemData for SaturationData {}
         let saturation = 7;
-        let mut item = Item::with_data(&item_type, SaturationData { saturation });
-        assert_eq!(*item.item_type, item_type);
+        let mut item = Item::with_data(item_type.clone(), SaturationData { saturation });
+        assert_eq!(item.item_type(), &item_type);
         assert_eq!(
             item.get_data::<SaturationData>().unwrap().saturation,
             saturation
@@ -202,7 +752,8 @@ mod tests {
 
     #[test]
     fn remove_data_from_item() {
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
+        #[allow(dead_code)] // only the presence of the data is checked, so its flag is never read
         struct SimpleData(bool);
         impl ItemData for SimpleData {}
         let mut item = item_type.item_with_data(SimpleData(true));
@@ -210,4 +761,267 @@ mod tests {
         item.remove_data::<SimpleData>();
         assert!(item.get_data::<SimpleData>().is_none());
     }
+
+    #[test]
+    fn remove_data_returns_it_once() {
+        struct SimpleData(bool);
+        impl ItemData for SimpleData {}
+
+        let mut item = Arc::new(ItemType::new()).item_with_data(SimpleData(true));
+        assert!(item.get_data::<SimpleData>().unwrap().0);
+        assert!(item.remove_data::<SimpleData>().unwrap().0);
+        assert!(item.remove_data::<SimpleData>().is_none());
+    }
+
+    #[test]
+    fn merge_stacks() {
+        let item_type = Arc::new(ItemType::with_data(MaxStackSize(10)));
+        let mut stack = item_type.item_new();
+        stack.set_count(4);
+        let mut other = item_type.item_new();
+        other.set_count(5);
+
+        assert!(stack.merge(other).is_none());
+        assert_eq!(stack.count(), 9);
+
+        let mut other = item_type.item_new();
+        other.set_count(5);
+        assert_eq!(stack.merge(other).unwrap().count(), 4);
+        assert_eq!(stack.count(), 10);
+    }
+
+    #[test]
+    fn merge_incompatible_stacks() {
+        let item_type = Arc::new(ItemType::with_data(MaxStackSize(10)));
+        let other_type = Arc::new(ItemType::with_data(MaxStackSize(10)));
+        struct SimpleData;
+        impl ItemData for SimpleData {}
+
+        let mut stack = item_type.item_new();
+        assert_eq!(stack.merge(other_type.item_new()).unwrap().count(), 1);
+        assert_eq!(
+            stack
+                .merge(item_type.item_with_data(SimpleData))
+                .unwrap()
+                .count(),
+            1
+        );
+        assert_eq!(stack.count(), 1);
+    }
+
+    #[test]
+    fn merge_stacks_with_equal_data() {
+        let item_type = Arc::new(ItemType::with_data(MaxStackSize(10)));
+        #[derive(PartialEq)]
+        struct Durability(u32);
+        impl ItemData for Durability {
+            fn stack_eq(&self, other: &dyn ItemData) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .is_some_and(|other| self == other)
+            }
+        }
+        struct Name;
+        impl ItemData for Name {}
+
+        let mut stack = item_type.item_with_data(Durability(5));
+        assert!(stack
+            .merge(item_type.item_with_data(Durability(5)))
+            .is_none());
+        assert!(stack
+            .merge(item_type.item_with_data(Durability(4)))
+            .is_some());
+        assert!(stack.merge(item_type.item_new()).is_some());
+        assert!(stack
+            .merge(item_type.item_with_data((Durability(5), Name)))
+            .is_some());
+        assert_eq!(stack.count(), 2);
+
+        let mut named = item_type.item_with_data(Name);
+        assert!(named.merge(item_type.item_with_data(Name)).is_some());
+    }
+
+    #[test]
+    fn item_overrides_max_stack_size() {
+        let item_type = Arc::new(ItemType::with_data(MaxStackSize(10)));
+        let item = item_type.item_with_data(MaxStackSize(20));
+        assert_eq!(item.max_stack_size(), 20);
+        assert_eq!(item_type.max_stack_size(), 10);
+    }
+
+    #[test]
+    fn get_many_data() {
+        struct Damage(u32);
+        impl ItemData for Damage {}
+        struct Durability(u32);
+        impl ItemData for Durability {}
+        struct Sharpness(u32);
+        impl ItemData for Sharpness {}
+
+        let sword = Arc::new(ItemType::with_data((Damage(7), Sharpness(1))));
+        let mut item = sword.item_with_data((Durability(50), Sharpness(4)));
+
+        let (damage, durability, sharpness) =
+            item.get_many::<(Damage, Durability, Sharpness)>().unwrap();
+        assert_eq!((damage.0, durability.0, sharpness.0), (7, 50, 4));
+        assert!(sword
+            .item_new()
+            .get_many::<(Damage, Durability)>()
+            .is_none());
+
+        // the damage is only on the type
+        assert!(item.get_many_mut::<(Durability, Damage)>().is_none());
+        let (sharpness, durability) = item.get_many_mut::<(Sharpness, Durability)>().unwrap();
+        durability.0 += sharpness.0;
+        sharpness.0 = 0;
+        assert_eq!(item.get_data::<Durability>().unwrap().0, 54);
+        assert_eq!(item.get_data::<Sharpness>().unwrap().0, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_many_mut_rejects_duplicate_types() {
+        struct Durability;
+        impl ItemData for Durability {}
+
+        let mut item = Arc::new(ItemType::new()).item_with_data(Durability);
+        let _ = item.get_many_mut::<(Durability, Durability)>();
+    }
+
+    #[test]
+    fn inherit_data_from_type() {
+        #[derive(Clone, PartialEq)]
+        struct Durability(u32);
+        impl ItemData for Durability {
+            fn stack_eq(&self, other: &dyn ItemData) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .is_some_and(|other| self == other)
+            }
+        }
+        impl InheritData for Durability {}
+
+        let sword = Arc::new(ItemType::with_data((Durability(100), MaxStackSize(4))));
+        let mut worn = sword.item_new();
+        worn.get_data_mut_or_inherit::<Durability>().unwrap().0 = 60;
+        assert_eq!(worn.get_data::<Durability>().unwrap().0, 60);
+        assert_eq!(sword.get_data::<Durability>().unwrap().0, 100);
+        assert!(!worn.can_stack_with(&sword.item_new()));
+
+        let mut other = sword.item_with_data(Durability(60));
+        assert_eq!(other.get_data_mut_or_inherit::<Durability>().unwrap().0, 60);
+        assert!(worn.can_stack_with(&other));
+
+        worn.remove_data::<Durability>();
+        assert_eq!(worn.get_data::<Durability>().unwrap().0, 100);
+        assert!(Arc::new(ItemType::new())
+            .item_new()
+            .get_data_mut_or_inherit::<Durability>()
+            .is_none());
+    }
+
+    #[test]
+    fn clone_and_split_items_with_data() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Enchantment(&'static str);
+        impl ItemData for Enchantment {
+            fn stack_eq(&self, other: &dyn ItemData) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .is_some_and(|other| self == other)
+            }
+
+            fn clone_data(&self) -> Option<Box<dyn ItemData>> {
+                Some(Box::new(self.clone()))
+            }
+
+            fn as_debug(&self) -> Option<&dyn std::fmt::Debug> {
+                Some(self)
+            }
+        }
+        struct Soulbound;
+        impl ItemData for Soulbound {}
+
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut stack = arrow.item_with_data(Enchantment("fire"));
+        stack.set_count(10);
+
+        let part = stack.split(4).unwrap();
+        assert_eq!((stack.count(), part.count()), (6, 4));
+        assert_eq!(
+            part.get_data::<Enchantment>().as_deref(),
+            Some(&Enchantment("fire"))
+        );
+        assert!(stack.can_stack_with(&part));
+
+        stack.add_data(Soulbound);
+        assert!(stack.try_clone().is_none());
+        assert!(stack.split(2).is_none());
+        assert_eq!(stack.count(), 6);
+        assert_eq!(
+            format!("{stack:?}"),
+            format!(
+                "Item {{ item_type: None, count: 6, data: [Enchantment(\"fire\"), {}] }}",
+                std::any::type_name::<Soulbound>()
+            )
+        );
+    }
+
+    #[test]
+    fn debug_item_types() {
+        let mut registry = ItemRegistry::new();
+        let arrow = registry.register(
+            "core:arrow",
+            ItemType::with_data(MaxStackSize(16)).with_tag("core:ammo"),
+        );
+        assert_eq!(
+            format!("{:?}", registry[arrow]),
+            "ItemType { key: Some(\"core:arrow\"), parent: None, tags: {\"core:ammo\"}, data: [MaxStackSize(16)] }"
+        );
+        let copy = registry[arrow].try_clone().unwrap();
+        assert_eq!(copy.key(), None);
+        assert!(copy.has_tag("core:ammo"));
+    }
+
+    #[test]
+    fn track_changes_of_count_and_data() {
+        struct Durability;
+        impl ItemData for Durability {}
+        struct Sharpness;
+        impl ItemData for Sharpness {}
+
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut item = arrow.item_with_data(Durability);
+        assert!(item.changed_since(Generation::current()));
+        assert_eq!(item.generation(), None);
+
+        item.track_changes();
+        let start = item.generation().unwrap();
+        assert_eq!(item.data_generation::<Durability>(), Some(start));
+
+        item.set_count(1);
+        assert!(!item.changed_since(start));
+        item.set_count(4);
+        assert!(item.changed_since(start));
+        assert!(!item.data_changed_since::<Durability>(start));
+
+        let counted = Generation::current();
+        item.add_data(Sharpness);
+        assert!(item.data_changed_since::<Sharpness>(counted));
+        let added = Generation::current();
+        let _ = item.get_many_mut::<(Durability, Sharpness)>().unwrap();
+        assert!(item.data_changed_since::<Durability>(added));
+        let borrowed = Generation::current();
+        item.remove_data::<Sharpness>();
+        assert!(item.data_changed_since::<Sharpness>(borrowed));
+        assert!(!item.data_changed_since::<Durability>(borrowed));
+
+        item.remove_data::<Durability>();
+        let removed = Generation::current();
+        let half = item.split(2).unwrap();
+        assert!(half.is_tracking_changes());
+        assert!(half.changed_since(removed));
+        assert!(item.changed_since(removed));
+        assert!(!half.data_changed_since::<Durability>(removed));
+    }
 }
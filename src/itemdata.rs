@@ -1,5 +1,161 @@
+use std::fmt;
+
 use downcast_rs::{impl_downcast, Downcast};
 
 /// Declares that a struct can be used as data for an `Item` or `ItemType`
-pub trait ItemData: Downcast + 'static {}
+///
+/// # Examples
+#[cfg_attr(feature = "proc", doc = "```")]
+#[cfg_attr(not(feature = "proc"), doc = "```ignore")]
+/// use std::sync::Arc;
+/// use shrub::{ItemData, ItemType, MaxStackSize};
+///
+/// #[derive(ItemData, PartialEq)]
+/// #[item_data(stack_eq)]
+/// struct Durability(u32);
+///
+/// let sword = Arc::new(ItemType::with_data(MaxStackSize(16)));
+/// let mut fresh = sword.item_with_data(Durability(100));
+/// assert!(fresh.merge(sword.item_with_data(Durability(100))).is_none());
+/// assert!(fresh.merge(sword.item_with_data(Durability(20))).is_some());
+/// ```
+///
+/// The derive can opt into all capabilities at once, see `shrub_macros::ItemData`:
+#[cfg_attr(feature = "proc", doc = "```")]
+#[cfg_attr(not(feature = "proc"), doc = "```ignore")]
+/// use std::sync::Arc;
+/// use shrub::{ItemData, ItemType, MaxStackSize};
+///
+/// #[derive(ItemData, Clone, Debug, PartialEq)]
+/// #[item_data(stack_eq, clone, debug, inherit)]
+/// struct Durability(u32);
+///
+/// let sword = Arc::new(ItemType::with_data((Durability(100), MaxStackSize(4))));
+/// let mut stack = sword.item_new();
+/// stack.set_count(4);
+/// stack.get_data_mut_or_inherit::<Durability>().unwrap().0 -= 10;
+///
+/// let half = stack.split(2).unwrap();
+/// assert_eq!(half.get_data::<Durability>().as_deref(), Some(&Durability(90)));
+/// assert!(format!("{half:?}").contains("Durability(90)"));
+/// ```
+pub trait ItemData: Downcast + Send + Sync + 'static {
+    /// Decides whether two items carrying this data may be merged into the same stack.
+    /// `other` always holds data of the same type as `self`.
+    ///
+    /// By default data is never equal, so items with data of this type won't stack.
+    /// Use `#[item_data(stack_eq)]` on the derive to compare with `PartialEq` instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::ItemData;
+    ///
+    /// #[derive(PartialEq)]
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {
+    ///     fn stack_eq(&self, other: &dyn ItemData) -> bool {
+    ///         other.downcast_ref::<Self>().is_some_and(|other| self == other)
+    ///     }
+    /// }
+    /// ```
+    fn stack_eq(&self, _other: &dyn ItemData) -> bool {
+        false
+    }
+
+    /// Duplicates the data, which lets items carrying it be cloned with `Item::try_clone` and split with `Item::split`.
+    /// The returned data must be of the same type as `self`.
+    ///
+    /// By default data can't be duplicated and `None` is returned.
+    /// Use `#[item_data(clone)]` on the derive to duplicate it with `Clone` instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::ItemData;
+    ///
+    /// #[derive(Clone)]
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {
+    ///     fn clone_data(&self) -> Option<Box<dyn ItemData>> {
+    ///         Some(Box::new(self.clone()))
+    ///     }
+    /// }
+    /// ```
+    fn clone_data(&self) -> Option<Box<dyn ItemData>> {
+        None
+    }
+
+    /// Exposes the `Debug` implementation of the data, so it shows up when an `Item` or `ItemType` is printed.
+    ///
+    /// By default `None` is returned and only the name of the data type is printed.
+    /// Use `#[item_data(debug)]` on the derive to print it with `Debug` instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::fmt::Debug;
+    /// use shrub::ItemData;
+    ///
+    /// #[derive(Debug)]
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {
+    ///     fn as_debug(&self) -> Option<&dyn Debug> {
+    ///         Some(self)
+    ///     }
+    /// }
+    /// ```
+    fn as_debug(&self) -> Option<&dyn fmt::Debug> {
+        None
+    }
+
+    /// Returns the name of the data type, as returned by `std::any::type_name`. There is no need to override this.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
 impl_downcast!(ItemData);
+
+/// Implements `ItemData` for the built-in data, which stacks when equal and can be cloned and printed,
+/// like `#[item_data(stack_eq, clone, debug)]` does on the derive.
+macro_rules! impl_value_item_data {
+    ($data:ty) => {
+        impl $crate::ItemData for $data {
+            fn stack_eq(&self, other: &dyn $crate::ItemData) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .is_some_and(|other| self == other)
+            }
+
+            fn clone_data(&self) -> Option<Box<dyn $crate::ItemData>> {
+                Some(Box::new(Clone::clone(self)))
+            }
+
+            fn as_debug(&self) -> Option<&dyn std::fmt::Debug> {
+                Some(self)
+            }
+        }
+    };
+}
+pub(crate) use impl_value_item_data;
+
+impl fmt::Debug for dyn ItemData {
+    /// Prints the data with its `Debug` implementation when it is exposed by `ItemData::as_debug`, or its type name otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_debug() {
+            Some(data) => data.fmt(f),
+            None => f.write_str(self.type_name()),
+        }
+    }
+}
+
+/// Marks `ItemData` that an `Item` may copy from its `ItemType` to change its own version of it,
+/// see `Item::get_data_mut_or_inherit`. Usually implemented with `#[item_data(inherit)]` on the derive.
+///
+/// # Examples
+/// ```
+/// use shrub::{InheritData, ItemData};
+///
+/// #[derive(Clone)]
+/// struct Durability(u32);
+/// impl ItemData for Durability {}
+/// impl InheritData for Durability {}
+/// ```
+pub trait InheritData: ItemData + Clone {}
@@ -1,11 +1,13 @@
 use std::any::TypeId;
 
-use crate::{item::ItemDataMap, ItemData};
+use crate::{item::ItemDataMap, DataRef, Item, ItemData};
 
 pub trait ItemDataReflection {
     const CAPACITY: usize;
 
     fn add_data(self, map: &mut ItemDataMap);
+
+    fn for_each_type_id(f: impl FnMut(TypeId));
 }
 
 impl ItemDataReflection for () {
@@ -13,6 +15,9 @@ impl ItemDataReflection for () {
 
     #[inline]
     fn add_data(self, _map: &mut ItemDataMap) {}
+
+    #[inline]
+    fn for_each_type_id(_f: impl FnMut(TypeId)) {}
 }
 
 impl<D: ItemData> ItemDataReflection for D {
@@ -22,8 +27,85 @@ impl<D: ItemData> ItemDataReflection for D {
     fn add_data(self, map: &mut ItemDataMap) {
         map.insert(TypeId::of::<D>(), Box::new(self));
     }
+
+    #[inline]
+    fn for_each_type_id(mut f: impl FnMut(TypeId)) {
+        f(TypeId::of::<D>());
+    }
+}
+
+/// A data type or tuple of data types that can be fetched from an `Item` at once, see `Item::get_many` and `InventoryItems::query`.
+pub trait ItemDataQuery {
+    /// References to the fetched data.
+    type Ref<'a>;
+    /// Mutable references to the fetched data.
+    type Mut<'a>;
+
+    /// Borrows the data from the item, falling back to its `ItemType` like `Item::get_data`.
+    /// Returns `None` when any of the data is missing.
+    fn fetch(item: &Item) -> Option<Self::Ref<'_>>;
+
+    /// Borrows the data of the item itself as mutable, without falling back to its `ItemType` like `Item::get_data_mut`.
+    /// Returns `None` when any of the data is missing.
+    ///
+    /// # Panics
+    /// Panics when a data type appears more than once in the tuple.
+    fn fetch_mut(item: &mut Item) -> Option<Self::Mut<'_>>;
+}
+
+impl<D: ItemData> ItemDataQuery for D {
+    type Ref<'a> = DataRef<'a, D>;
+    type Mut<'a> = &'a mut D;
+
+    #[inline]
+    fn fetch(item: &Item) -> Option<Self::Ref<'_>> {
+        item.get_data::<D>()
+    }
+
+    #[inline]
+    fn fetch_mut(item: &mut Item) -> Option<Self::Mut<'_>> {
+        item.get_data_mut::<D>()
+    }
 }
 
+macro_rules! impl_itemdata_query {
+    ( $(($generic:ident, $var:ident))+ ) => {
+        impl<$($generic: ItemData),+> ItemDataQuery for ($($generic,)+) {
+            type Ref<'a> = ($(DataRef<'a, $generic>,)+);
+            type Mut<'a> = ($(&'a mut $generic,)+);
+
+            #[inline]
+            fn fetch(item: &Item) -> Option<Self::Ref<'_>> {
+                Some(($(item.get_data::<$generic>()?,)+))
+            }
+
+            #[inline]
+            fn fetch_mut(item: &mut Item) -> Option<Self::Mut<'_>> {
+                let (data, changes) = item.data_and_changes();
+                let [$($var),+] = data.get_disjoint_mut([$(&TypeId::of::<$generic>()),+]);
+                let fetched = ($($var?.downcast_mut::<$generic>()?,)+);
+                if let Some(changes) = changes {
+                    $(
+                        changes.data_changed(TypeId::of::<$generic>());
+                    )+
+                }
+                Some(fetched)
+            }
+        }
+    };
+}
+
+impl_itemdata_query!((A, a));
+impl_itemdata_query!((A, a)(B, b));
+impl_itemdata_query!((A, a)(B, b)(C, c));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e)(F, f));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e)(F, f)(G, g));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e)(F, f)(G, g)(H, h));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e)(F, f)(G, g)(H, h)(I, i));
+impl_itemdata_query!((A, a)(B, b)(C, c)(D, d)(E, e)(F, f)(G, g)(H, h)(I, i)(J, j));
+
 macro_rules! replace_expr {
     ($_t:tt $sub:expr) => {
         $sub
@@ -41,6 +123,13 @@ macro_rules! impl_itemdata_reflection {
                     map.insert(TypeId::of::<$generic>(), Box::new(self.$index));
                 )+
             }
+
+            #[inline]
+            fn for_each_type_id(mut f: impl FnMut(TypeId)) {
+                $(
+                    f(TypeId::of::<$generic>());
+                )+
+            }
         }
     };
 }
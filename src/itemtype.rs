@@ -1,38 +1,312 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, PoisonError, RwLock},
+};
 
-use crate::{item::ItemDataMap, itemdata_reflection::ItemDataReflection, Item, ItemData};
+use crate::{
+    item::{clone_data_map, DebugDataMap, ItemDataMap},
+    itemdata_reflection::ItemDataReflection,
+    registry::RegistryIdentity,
+    DataRef, Item, ItemData, ItemTypeId, MaxStackSize,
+};
 
 /// An ItemType holds data that is used by and for creating `Item`s of the type.
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// ItemTypes are usually owned by an `ItemRegistry`, which gives them their `ItemTypeId`.
+///
+/// Besides data, ItemTypes can carry tags like `core:logs`, which put them into categories without a dedicated `ItemData`.
+/// Tags may be written with a leading `#`, which is ignored.
+///
+/// An ItemType can derive from a parent type, like `iron_sword` from `sword` from `weapon`.
+/// Data and tags the ItemType doesn't have itself are then looked up along its parents, see `ItemType::with_parent`.
+///
+/// # Examples
+/// ```
+/// use shrub::ItemType;
+///
+/// let oak_log = ItemType::new().with_tag("#core:logs").with_tag("core:burnable");
+/// assert!(oak_log.has_tag("core:logs"));
+/// assert!(oak_log.any_of(["core:planks", "core:logs"]));
+/// assert!(!oak_log.all_of(["core:logs", "core:planks"]));
+/// ```
 pub struct ItemType {
-    data: ItemDataMap,
+    id: Option<ItemTypeId>,
+    /// The `ItemRegistry` the id belongs to, as ids of different registries overlap.
+    registry: Option<RegistryIdentity>,
+    key: Option<Box<str>>,
+    /// Swapped as a whole by `ItemRegistry::replace`, so readers always see a single version of it.
+    definition: RwLock<Arc<Definition>>,
+}
+
+/// The tags, parent and data of an `ItemType`, which can be replaced while the ItemType is shared.
+#[derive(Clone, Default)]
+pub(crate) struct Definition {
+    pub(crate) tags: BTreeSet<Box<str>>,
+    pub(crate) parent: Option<Arc<ItemType>>,
+    /// Shared with `DataRef`s, which keep a replaced version of the data alive as long as they need it.
+    pub(crate) data: Arc<ItemDataMap>,
 }
 
 impl std::fmt::Debug for ItemType {
-    // TODO: better impl for debug, maybe only print debug for itemdata which implement debug if possible
+    /// Prints the key, the tags and the data of the ItemType, see `ItemData::as_debug`.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ItemType")
+        let definition = self.definition();
+        f.debug_struct("ItemType")
+            .field("key", &self.key())
+            .field(
+                "parent",
+                &definition.parent.as_ref().map(|parent| parent.key()),
+            )
+            .field("tags", &definition.tags)
+            .field("data", &DebugDataMap(&definition.data))
+            .finish()
+    }
+}
+
+impl Default for ItemType {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl ItemType {
     /// Creates a new ItemType, the HashMap for the data will not allocate until it is first inserted into.
     pub fn new() -> Self {
-        let data = HashMap::new();
-        ItemType { data }
+        Self::with_definition(Definition::default())
     }
 
     /// Creates a new ItemType with the given data for the item.
     pub fn with_data<D: ItemDataReflection>(item_data: D) -> Self {
         let mut data = HashMap::with_capacity(D::CAPACITY);
         item_data.add_data(&mut data);
-        ItemType { data }
+        Self::with_definition(Definition {
+            data: Arc::new(data),
+            ..Definition::default()
+        })
     }
 
     /// Creates a new ItemType and reserves the given amount of capacity for the data map.
     pub fn with_capacity(data_capacity: usize) -> Self {
-        let data = HashMap::with_capacity(data_capacity);
-        ItemType { data }
+        Self::with_definition(Definition {
+            data: Arc::new(HashMap::with_capacity(data_capacity)),
+            ..Definition::default()
+        })
+    }
+
+    fn with_definition(definition: Definition) -> Self {
+        ItemType {
+            id: None,
+            registry: None,
+            key: None,
+            definition: RwLock::new(Arc::new(definition)),
+        }
+    }
+
+    /// Duplicates the ItemType with its tags, data and parent, see `ItemData::clone_data`.
+    /// The copy isn't registered, so it can be changed and registered under another key.
+    /// Returns `None` when any of the data can't be duplicated.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, ItemType, MaxStackSize};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let arrow = registry.register("core:arrow", ItemType::with_data(MaxStackSize(64)));
+    /// let mut fire_arrow = registry[arrow].try_clone().unwrap();
+    /// fire_arrow.add_tag("core:burning");
+    /// let fire_arrow = registry.register("core:fire_arrow", fire_arrow);
+    /// assert_eq!(registry[fire_arrow].max_stack_size(), 64);
+    /// ```
+    pub fn try_clone(&self) -> Option<ItemType> {
+        let definition = self.definition();
+        Some(Self::with_definition(Definition {
+            tags: definition.tags.clone(),
+            parent: definition.parent.clone(),
+            data: Arc::new(clone_data_map(&definition.data)?),
+        }))
+    }
+
+    /// Marks the ItemType as registered under the given id and key.
+    pub(crate) fn register(&mut self, registry: RegistryIdentity, id: ItemTypeId, key: &str) {
+        self.registry = Some(registry);
+        self.id = Some(id);
+        self.key = Some(key.into());
+    }
+
+    /// Creates a stand-in for an ItemType that isn't registered anymore, which remembers the key it was saved under.
+    #[cfg(feature = "serde")]
+    pub(crate) fn placeholder(key: &str) -> Self {
+        ItemType {
+            key: Some(key.into()),
+            ..Self::new()
+        }
+    }
+
+    /// Returns `true` when the ItemType stands in for a type that was missing from the `ItemRegistry` while loading,
+    /// see `UnknownPolicy::Preserve`.
+    #[inline]
+    pub fn is_placeholder(&self) -> bool {
+        self.id.is_none() && self.key.is_some()
+    }
+
+    /// Returns the id of the ItemType, or `None` when it isn't registered in an `ItemRegistry`.
+    #[inline]
+    pub fn id(&self) -> Option<ItemTypeId> {
+        self.id
+    }
+
+    /// Returns the key the ItemType was registered under, or `None` when it isn't registered in an `ItemRegistry`.
+    /// Placeholders return the key they were saved under.
+    #[inline]
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Returns the current version of the tags, parent and data.
+    pub(crate) fn definition(&self) -> Arc<Definition> {
+        self.definition
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Iterates over the definitions of the ItemType and its parents, from the closest to the most distant one.
+    fn definitions(&self) -> impl Iterator<Item = Arc<Definition>> {
+        std::iter::successors(Some(self.definition()), |definition| {
+            Some(definition.parent.as_ref()?.definition())
+        })
+    }
+
+    fn definition_mut(&mut self) -> &mut Definition {
+        Arc::make_mut(
+            self.definition
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Borrows the data map of the ItemType itself as mutable.
+    pub(crate) fn data_mut(&mut self) -> &mut ItemDataMap {
+        // `DataRef`s borrow the ItemType, an Item of it or a type deriving from it, so none are left
+        Arc::get_mut(&mut self.definition_mut().data).expect("item type data is still borrowed")
+    }
+
+    /// Puts the definition in place of the current one, see `ItemRegistry::replace`.
+    /// The previous definition is dropped as soon as nothing reads from it anymore.
+    pub(crate) fn redefine(&self, definition: Definition) {
+        *self
+            .definition
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(definition);
+    }
+
+    /// Takes the definition out of an ItemType that is about to replace a registered one.
+    pub(crate) fn into_definition(self) -> Definition {
+        let definition = self
+            .definition
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::unwrap_or_clone(definition)
+    }
+
+    /// Adds the tags of tag groups to a registered ItemType, see `ItemRegistry::add_tag_group`.
+    pub(crate) fn add_group_tags(&self, groups: BTreeSet<Box<str>>) {
+        let mut definition = Definition::clone(&self.definition());
+        definition.tags.extend(groups);
+        self.redefine(definition);
+    }
+
+    /// Adds the tag to the ItemType and returns it.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Adds the tag to the ItemType. Returns `false` when it already had the tag.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        self.definition_mut().tags.insert(tag_key(tag).into())
+    }
+
+    /// Removes the tag from the ItemType. Returns `false` when it didn't have the tag.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        self.definition_mut().tags.remove(tag_key(tag))
+    }
+
+    /// Returns `true` when the ItemType or one of its parents has the tag, given directly or through a tag group of its `ItemRegistry`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag_key(tag);
+        self.definitions()
+            .any(|definition| definition.tags.contains(tag))
+    }
+
+    /// Returns `true` when the ItemType has at least one of the tags.
+    pub fn any_of<'a>(&self, tags: impl IntoIterator<Item = &'a str>) -> bool {
+        tags.into_iter().any(|tag| self.has_tag(tag))
+    }
+
+    /// Returns `true` when the ItemType has all of the tags.
+    pub fn all_of<'a>(&self, tags: impl IntoIterator<Item = &'a str>) -> bool {
+        tags.into_iter().all(|tag| self.has_tag(tag))
+    }
+
+    /// Iterates over the tags of the ItemType and its parents in alphabetical order.
+    pub fn tags(&self) -> impl Iterator<Item = String> {
+        self.definitions()
+            .flat_map(|definition| {
+                definition
+                    .tags
+                    .iter()
+                    .map(|tag| tag.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Makes the ItemType derive from the parent and returns it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemData, ItemType, MaxStackSize};
+    ///
+    /// struct Damage(u32);
+    /// impl ItemData for Damage {}
+    ///
+    /// let weapon = Arc::new(ItemType::with_data(Damage(1)).with_tag("core:weapons"));
+    /// let sword = Arc::new(ItemType::with_data(Damage(5)).with_parent(weapon.clone()));
+    /// let iron_sword = Arc::new(ItemType::with_data(MaxStackSize(1)).with_parent(sword.clone()));
+    ///
+    /// assert_eq!(iron_sword.get_data::<Damage>().unwrap().0, 5); // the sword overrides the weapon
+    /// assert!(iron_sword.has_tag("core:weapons"));
+    /// assert!(Arc::ptr_eq(&iron_sword.data_source::<Damage>().unwrap(), &sword));
+    /// ```
+    pub fn with_parent(mut self, parent: Arc<ItemType>) -> Self {
+        self.set_parent(Some(parent));
+        self
+    }
+
+    /// Changes the parent the ItemType derives from.
+    #[inline]
+    pub fn set_parent(&mut self, parent: Option<Arc<ItemType>>) {
+        self.definition_mut().parent = parent;
+    }
+
+    /// Returns the parent the ItemType derives from.
+    #[inline]
+    pub fn parent(&self) -> Option<Arc<ItemType>> {
+        self.definition().parent.clone()
+    }
+
+    /// Iterates over the ItemType itself followed by its parents, from the closest to the most distant one.
+    pub fn ancestors(self: &Arc<Self>) -> impl Iterator<Item = Arc<ItemType>> {
+        std::iter::successors(Some(self.clone()), |item_type| item_type.parent())
+    }
+
+    /// Returns the ItemType along the parents, starting with this one, that supplies the data of the given datatype.
+    pub fn data_source<D: ItemData>(self: &Arc<Self>) -> Option<Arc<ItemType>> {
+        self.ancestors()
+            .find(|item_type| item_type.definition().data.contains_key(&TypeId::of::<D>()))
     }
 
     /// Adds the given data to the ItemType. When data with the given datatype already exists, it will be completly overriden.
@@ -52,11 +326,15 @@ impl ItemType {
     /// ```
     #[inline]
     pub fn add_data<D: ItemDataReflection>(&mut self, item_data: D) {
-        self.data.reserve(D::CAPACITY);
-        item_data.add_data(&mut self.data);
+        let data = self.data_mut();
+        data.reserve(D::CAPACITY);
+        item_data.add_data(data);
     }
 
-    /// Borrows data of the given datatype from the ItemType.
+    /// Borrows data of the given datatype from the ItemType. When the ItemType doesn't have data from this datatype,
+    /// it searches along its parents.
+    ///
+    /// The data stays readable when the ItemType is redefined meanwhile, see `ItemRegistry::replace`.
     ///
     /// # Examples
     /// ```
@@ -71,14 +349,12 @@ impl ItemType {
     /// assert_eq!(item_type.get_data::<CoolData>().unwrap().is_cool, true);
     /// ```
     #[inline]
-    pub fn get_data<D: ItemData>(&self) -> Option<&D> {
-        match self.data.get(&TypeId::of::<D>()) {
-            Some(d) => Some(d.as_any().downcast_ref::<D>().unwrap()),
-            None => None,
-        }
+    pub fn get_data<D: ItemData>(&self) -> Option<DataRef<'_, D>> {
+        self.definitions()
+            .find_map(|definition| DataRef::shared(definition.data.clone()))
     }
 
-    /// Borrows data fo the given datatype from the ItemType as mutable.
+    /// Borrows data fo the given datatype from the ItemType as mutable. Data of the parents can't be borrowed mutably.
     ///
     /// # Examples
     /// ```
@@ -96,10 +372,9 @@ impl ItemType {
     /// ```
     #[inline]
     pub fn get_data_mut<D: ItemData>(&mut self) -> Option<&mut D> {
-        match self.data.get_mut(&TypeId::of::<D>()) {
-            Some(d) => Some(d.as_any_mut().downcast_mut::<D>().unwrap()),
-            None => None,
-        }
+        self.data_mut()
+            .get_mut(&TypeId::of::<D>())?
+            .downcast_mut::<D>()
     }
 
     /// Remove data of passed type from itemtype
@@ -116,44 +391,61 @@ impl ItemType {
     /// assert!(item_type.get_data::<SimpleData>().is_none());
     /// ```
     pub fn remove_data<D: ItemData>(&mut self) -> Option<Box<D>> {
-        match self.data.remove(&TypeId::of::<D>()) {
-            Some(d) => Some(d.downcast::<D>().ok().unwrap()),
-            None => None,
-        }
+        self.data_mut()
+            .remove(&TypeId::of::<D>())
+            .and_then(|d| d.downcast::<D>().ok())
+    }
+
+    /// Returns the maximum amount of items a stack of this type can hold, read from its `MaxStackSize` data.
+    /// Types without `MaxStackSize` don't stack and return 1.
+    #[inline]
+    pub fn max_stack_size(&self) -> u32 {
+        self.get_data::<MaxStackSize>().map_or(1, |max| max.0)
     }
 
     /// Creates a new Item from this type.
     /// The HashMap for the itemdata will not allocate until it is first inserted into.
-    pub fn item_new(&self) -> Item {
-        Item::new(self)
+    pub fn item_new(self: &Arc<Self>) -> Item {
+        Item::new(self.clone())
     }
 
     /// Creates a new Item from this type with the given data.
-    pub fn item_with_data<D: ItemDataReflection>(&self, item_data: D) -> Item {
-        Item::with_data(self, item_data)
+    pub fn item_with_data<D: ItemDataReflection>(self: &Arc<Self>, item_data: D) -> Item {
+        Item::with_data(self.clone(), item_data)
     }
 
     /// Creates a new Item from this type and reserves the given amount of capacity for the data map.
-    pub fn item_with_capacity(&self, data_capacity: usize) -> Item {
-        Item::with_capacity(self, data_capacity)
+    pub fn item_with_capacity(self: &Arc<Self>, data_capacity: usize) -> Item {
+        Item::with_capacity(self.clone(), data_capacity)
     }
 }
 
+/// Strips the optional `#` in front of a tag.
+pub(crate) fn tag_key(tag: &str) -> &str {
+    tag.strip_prefix('#').unwrap_or(tag)
+}
+
 impl PartialEq for ItemType {
+    /// ItemTypes of the same `ItemRegistry` are equal when their ids are, all others only equal themselves.
     fn eq(&self, other: &ItemType) -> bool {
-        std::ptr::eq(self, other)
+        match (self.id, other.id) {
+            (Some(id), Some(other_id)) if self.registry == other.registry => id == other_id,
+            _ => std::ptr::eq(self, other),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ItemData, ItemType};
+    use std::sync::Arc;
+
+    use crate::{DataSource, ItemData, ItemRegistry, ItemType, MaxStackSize};
 
     #[test]
     fn create_item() {
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         let item = item_type.item_new();
-        assert_eq!(item.item_type, &item_type);
+        assert_eq!(item.item_type(), &item_type);
     }
 
     #[test]
@@ -163,12 +455,12 @@ mod tests {
         }
         impl ItemData for SaturationData {}
 
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         let saturation = 8;
         let mut item = item_type.item_with_capacity(1);
         item.add_data(SaturationData { saturation });
 
-        assert_eq!(item.item_type, &item_type);
+        assert_eq!(item.item_type(), &item_type);
         assert_eq!(
             item.get_data::<SaturationData>().unwrap().saturation,
             saturation
@@ -186,11 +478,11 @@ mod tests {
         }
         impl ItemData for SpeedData {}
 
-        let item_type = ItemType::new();
+        let item_type = Arc::new(ItemType::new());
         let saturation = 12;
         let speed = 13.4;
         let item = item_type.item_with_data((SaturationData { saturation }, SpeedData { speed }));
-        assert_eq!(*item.item_type, item_type);
+        assert_eq!(item.item_type(), &item_type);
         assert_eq!(
             item.get_data::<SaturationData>().unwrap().saturation,
             saturation
@@ -206,7 +498,7 @@ mod tests {
         impl ItemData for CoolData {}
 
         let is_cool = true;
-        let item_type = ItemType::with_data(CoolData { is_cool });
+        let item_type = Arc::new(ItemType::with_data(CoolData { is_cool }));
         assert_eq!(item_type.get_data::<CoolData>().unwrap().is_cool, is_cool);
         let item = item_type.item_new();
         assert_eq!(item.get_data::<CoolData>().unwrap().is_cool, is_cool);
@@ -222,9 +514,9 @@ mod tests {
         let type_number = 12.5;
         let item_number = 74.12;
 
-        let item_type = ItemType::with_data(NumberData {
+        let item_type = Arc::new(ItemType::with_data(NumberData {
             number: type_number,
-        });
+        }));
         let item = item_type.item_with_data(NumberData {
             number: item_number,
         });
@@ -234,4 +526,58 @@ mod tests {
         );
         assert_eq!(item.get_data::<NumberData>().unwrap().number, item_number);
     }
+
+    #[test]
+    fn look_up_data_along_parents() {
+        struct Damage(u32);
+        impl ItemData for Damage {}
+        struct Durability(u32);
+        impl ItemData for Durability {}
+
+        let mut registry = ItemRegistry::new();
+        let weapon = registry.register(
+            "core:weapon",
+            ItemType::with_data((Damage(1), Durability(50))).with_tag("core:weapons"),
+        );
+        let sword = registry.register(
+            "core:sword",
+            ItemType::with_data(Damage(5)).with_parent(registry[weapon].clone()),
+        );
+        let iron_sword = registry.register(
+            "core:iron_sword",
+            ItemType::with_data(Durability(250))
+                .with_parent(registry[sword].clone())
+                .with_tag("core:iron"),
+        );
+        let iron_sword = &registry[iron_sword];
+
+        assert_eq!(
+            iron_sword
+                .ancestors()
+                .map(|item_type| item_type.key().unwrap().to_owned())
+                .collect::<Vec<_>>(),
+            vec!["core:iron_sword", "core:sword", "core:weapon"]
+        );
+        assert_eq!(iron_sword.get_data::<Damage>().unwrap().0, 5);
+        assert_eq!(iron_sword.get_data::<Durability>().unwrap().0, 250);
+        assert_eq!(
+            iron_sword.data_source::<Damage>().unwrap().key(),
+            Some("core:sword")
+        );
+        assert_eq!(
+            iron_sword.tags().collect::<Vec<_>>(),
+            vec!["core:iron", "core:weapons"]
+        );
+        assert!(registry[sword].all_of(["core:weapons"]));
+        assert!(!registry[sword].has_tag("core:iron"));
+
+        let item = iron_sword.item_with_data(Damage(9));
+        assert_eq!(item.get_data::<Damage>().unwrap().0, 9);
+        assert_eq!(item.data_source::<Damage>(), Some(DataSource::Item));
+        assert_eq!(
+            item.data_source::<Durability>(),
+            Some(DataSource::ItemType(iron_sword.clone()))
+        );
+        assert!(item.data_source::<MaxStackSize>().is_none());
+    }
 }
@@ -2,18 +2,67 @@
 //!
 //! # Features
 //! - **proc** *(default)* &mdash; re-exports procedural macros from `shrub_macros`
-//! - **serde** &mdash; derives `Serialize` and `Deserialize` traits
+//! - **serde** &mdash; implements `Serialize` and `Deserialize` for `Item` and `ItemType`, see `ItemRegistry::register_data` and `ItemRegistry::load_scope`
+//! - **json**, **ron**, **toml** &mdash; load item definitions from files of the format, see `ItemRegistry::load_definitions_file`
+mod capacity;
+mod category_inventory;
+mod changes;
+#[cfg(feature = "serde")]
+mod definitions;
+mod error;
+mod filter;
+mod grid_inventory;
 mod inventory;
 mod item;
 mod itemdata;
 mod itemdata_reflection;
 mod itemtype;
+#[cfg(feature = "serde")]
+mod load;
+mod observed;
+mod registry;
+#[cfg(feature = "serde")]
+mod save;
+#[cfg(feature = "serde")]
+mod serialization;
+mod slot_inventory;
+mod stack;
+mod transaction;
+mod transfer;
 
+pub use capacity::{CapacityInventory, Volume, Weight};
+pub use category_inventory::CategoryInventory;
+pub use changes::Generation;
+#[cfg(any(feature = "json", feature = "ron", feature = "toml"))]
+pub use definitions::DefinitionFiles;
+#[cfg(feature = "serde")]
+pub use definitions::{DefinitionError, DefinitionErrorKind, ItemTypeChange, ReloadReport};
+pub use error::{ShrubError, ShrubErrorKind};
+pub use filter::ItemFilter;
+pub use grid_inventory::{Footprint, GridInventory};
 pub use inventory::Inventory;
+pub use inventory::InventoryEntries;
+pub use inventory::InventoryItems;
 pub use inventory::InventorySelector;
-pub use item::Item;
-pub use itemdata::ItemData;
+pub use item::{DataRef, DataSource, Item};
+pub use itemdata::{InheritData, ItemData};
+pub use itemdata_reflection::ItemDataQuery;
 pub use itemtype::ItemType;
+#[cfg(feature = "serde")]
+pub use load::{LoadReport, PreservedData, UnknownPolicy};
+pub use observed::{InventoryEvent, Observed};
+pub use registry::ItemRegistry;
+pub use registry::ItemTypeId;
+#[cfg(feature = "serde")]
+pub use save::{SaveGame, SAVE_FORMAT_VERSION};
+#[cfg(feature = "serde")]
+pub use serialization::SerdeData;
+pub use slot_inventory::SlotInventory;
+pub use stack::MaxStackSize;
+pub use transaction::{
+    AddReceipt, InventoryHandle, Transaction, TransactionError, TransactionInventory,
+};
+pub use transfer::{transfer, transfer_amount};
 
 #[cfg(feature = "proc")]
 pub use shrub_macros::ItemData;
@@ -0,0 +1,428 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::{ItemData, ItemRegistry, ItemType};
+
+/// What happens to item data tags and item type keys of loaded items which aren't registered in the `ItemRegistry`,
+/// for example because the mod which added them was removed.
+///
+/// Dropping or preserving unknown item data requires a self-describing format like JSON,
+/// with formats like bincode unknown data is always an error. Missing item types are supported by every format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownPolicy {
+    /// Loading fails with an error, like it does outside of `ItemRegistry::load_scope`.
+    #[default]
+    Error,
+    /// Unknown data is skipped and items of missing types are removed from the `SlotInventory`s they are loaded into.
+    /// Items that aren't inside of a `SlotInventory` are kept as placeholders like with `Preserve`.
+    Drop,
+    /// Unknown data is kept as `PreservedData` and items of missing types get a placeholder `ItemType`,
+    /// see `ItemType::is_placeholder`. Both are written back unchanged when the items are saved again.
+    Preserve,
+}
+
+/// Lists the unknown item data tags and item type keys encountered by `ItemRegistry::load_scope`,
+/// together with how often they were encountered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LoadReport {
+    policy: UnknownPolicy,
+    data: BTreeMap<String, usize>,
+    item_types: BTreeMap<String, usize>,
+}
+
+impl LoadReport {
+    /// Returns the policy which was applied to everything in the report.
+    #[inline]
+    pub fn policy(&self) -> UnknownPolicy {
+        self.policy
+    }
+
+    /// Returns `true` when everything that was loaded is registered.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.item_types.is_empty()
+    }
+
+    /// Iterates over the unknown item data tags, sorted by tag, together with the amount of items and item types carrying them.
+    pub fn unknown_data(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.data.iter().map(|(tag, count)| (tag.as_str(), *count))
+    }
+
+    /// Iterates over the missing item type keys, sorted by key, together with the amount of items of the type.
+    pub fn missing_item_types(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.item_types
+            .iter()
+            .map(|(key, count)| (key.as_str(), *count))
+    }
+}
+
+/// Item data of unknown tags, kept as raw values by `UnknownPolicy::Preserve`.
+///
+/// Maps inside of the data are written back with sorted keys.
+/// Data loaded from a `SaveGame` keeps the schema version it was saved with. When that isn't version 0, the data is
+/// written back under its tag followed by `@` and the version, like `mod:enchantment@2`, so it is still migrated
+/// correctly once its tag is registered again, even when other items carry the same data at another version.
+#[derive(Debug, Default, Clone)]
+pub struct PreservedData {
+    pub(crate) entries: BTreeMap<String, serde_value::Value>,
+    /// The schema versions other than 0 the entries were saved with.
+    pub(crate) versions: BTreeMap<String, u32>,
+}
+
+impl ItemData for PreservedData {
+    fn clone_data(&self) -> Option<Box<dyn ItemData>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn as_debug(&self) -> Option<&dyn std::fmt::Debug> {
+        Some(self)
+    }
+}
+
+impl PreservedData {
+    /// Iterates over the tags of the preserved data, sorted by tag.
+    pub fn tags(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Returns `true` when data with the given tag was preserved.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.entries.contains_key(tag)
+    }
+
+    /// Returns the schema version the data with the given tag was saved with, or `None` when it wasn't preserved.
+    /// Data that wasn't loaded from a `SaveGame` has version 0.
+    pub fn version(&self, tag: &str) -> Option<u32> {
+        self.contains(tag)
+            .then(|| self.versions.get(tag).copied().unwrap_or(0))
+    }
+}
+
+struct LoadState {
+    report: LoadReport,
+    placeholders: HashMap<String, Arc<ItemType>>,
+}
+
+thread_local! {
+    static LOAD: RefCell<Option<LoadState>> = const { RefCell::new(None) };
+}
+
+impl ItemRegistry {
+    /// Like `serde_scope`, but unknown item data tags and missing item types are handled according to the policy.
+    /// Returns the result of `f` together with a report of everything that wasn't registered.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{InventorySelector, ItemRegistry, ItemType, SlotInventory, UnknownPolicy};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// registry.register("core:sword", ItemType::new());
+    ///
+    /// // saved while the `magic` mod was installed
+    /// let save = r#"{ "slots": [
+    ///     { "type": "core:sword", "count": 1, "data": { "magic:enchantment": "sharpness" } },
+    ///     { "type": "magic:wand", "count": 1, "data": {} }
+    /// ] }"#;
+    /// let (inventory, report) = registry.load_scope(UnknownPolicy::Drop, || {
+    ///     serde_json::from_str::<SlotInventory>(save)
+    /// });
+    /// let inventory = inventory.unwrap();
+    /// assert!(inventory.get_item(0).is_some());
+    /// assert!(inventory.get_item(1).is_none());
+    /// assert_eq!(report.unknown_data().collect::<Vec<_>>(), vec![("magic:enchantment", 1)]);
+    /// assert_eq!(report.missing_item_types().collect::<Vec<_>>(), vec![("magic:wand", 1)]);
+    /// ```
+    pub fn load_scope<R>(&self, policy: UnknownPolicy, f: impl FnOnce() -> R) -> (R, LoadReport) {
+        struct Restore(Option<Option<LoadState>>);
+        impl Restore {
+            fn finish(mut self) -> Option<LoadState> {
+                let previous = self.0.take().unwrap();
+                LOAD.with(|load| load.replace(previous))
+            }
+        }
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                if let Some(previous) = self.0.take() {
+                    LOAD.with(|load| *load.borrow_mut() = previous);
+                }
+            }
+        }
+
+        let state = LoadState {
+            report: LoadReport {
+                policy,
+                ..LoadReport::default()
+            },
+            placeholders: HashMap::new(),
+        };
+        let restore = Restore(Some(LOAD.with(|load| load.replace(Some(state)))));
+        let result = self.serde_scope(f);
+        let report = restore
+            .finish()
+            .map(|state| state.report)
+            .unwrap_or_default();
+        (result, report)
+    }
+}
+
+/// Returns the policy of the innermost `load_scope`.
+pub(crate) fn unknown_policy() -> UnknownPolicy {
+    LOAD.with(|load| {
+        load.borrow()
+            .as_ref()
+            .map_or(UnknownPolicy::Error, |state| state.report.policy)
+    })
+}
+
+/// Records unknown item data in the report of the innermost `load_scope`.
+pub(crate) fn report_unknown_data(tag: &str) {
+    LOAD.with(|load| {
+        if let Some(state) = load.borrow_mut().as_mut() {
+            *state.report.data.entry(tag.to_owned()).or_default() += 1;
+        }
+    });
+}
+
+/// Returns the placeholder for a missing item type and records it in the report of the innermost `load_scope`.
+/// Items of the same missing type share their placeholder, so they still stack with each other.
+/// Returns `None` when missing item types are an error.
+pub(crate) fn placeholder_type(key: &str) -> Option<Arc<ItemType>> {
+    LOAD.with(|load| {
+        let mut load = load.borrow_mut();
+        let state = load.as_mut()?;
+        if state.report.policy == UnknownPolicy::Error {
+            return None;
+        }
+        *state.report.item_types.entry(key.to_owned()).or_default() += 1;
+        let placeholder = state
+            .placeholders
+            .entry(key.to_owned())
+            .or_insert_with(|| Arc::new(ItemType::placeholder(key)));
+        Some(placeholder.clone())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        Inventory, InventorySelector, Item, ItemData, ItemRegistry, ItemType, MaxStackSize,
+        PreservedData, SaveGame, SlotInventory, UnknownPolicy,
+    };
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Durability(u32);
+    impl ItemData for Durability {}
+
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::new();
+        registry.register_data::<Durability>("test:durability");
+        registry.register("test:sword", ItemType::new());
+        registry
+    }
+
+    const SAVE: &str = r#"{"slots":[{"type":"test:sword","count":1,"data":{"mod:enchantment":{"level":2,"name":"sharpness"},"test:durability":7}},null,{"type":"mod:wand","count":3,"data":{"shrub:max_stack_size":8}},{"type":"mod:wand","count":2,"data":{"shrub:max_stack_size":8}}]}"#;
+
+    #[test]
+    fn error_on_unknown() {
+        let registry = registry();
+        let (result, report) = registry.load_scope(UnknownPolicy::Error, || {
+            serde_json::from_str::<SlotInventory>(SAVE)
+        });
+        assert!(result.is_err());
+        assert!(report.is_empty());
+        assert!(registry
+            .serde_scope(|| serde_json::from_str::<SlotInventory>(SAVE))
+            .is_err());
+    }
+
+    #[test]
+    fn drop_unknown() {
+        let registry = registry();
+        let (inventory, report) = registry.load_scope(UnknownPolicy::Drop, || {
+            serde_json::from_str::<SlotInventory>(SAVE)
+        });
+        let inventory = inventory.unwrap();
+        assert_eq!(inventory.capacity(), 4);
+        assert_eq!(inventory.len(), 1);
+        let sword = inventory.get_item(0).unwrap();
+        assert_eq!(
+            sword.get_data::<Durability>().as_deref(),
+            Some(&Durability(7))
+        );
+        assert!(sword.get_data::<PreservedData>().is_none());
+
+        assert_eq!(report.policy(), UnknownPolicy::Drop);
+        assert_eq!(
+            report.unknown_data().collect::<Vec<_>>(),
+            vec![("mod:enchantment", 1)]
+        );
+        assert_eq!(
+            report.missing_item_types().collect::<Vec<_>>(),
+            vec![("mod:wand", 2)]
+        );
+    }
+
+    #[test]
+    fn preserve_unknown() {
+        let registry = registry();
+        let (inventory, report) = registry.load_scope(UnknownPolicy::Preserve, || {
+            serde_json::from_str::<SlotInventory>(SAVE)
+        });
+        let mut inventory = inventory.unwrap();
+        assert_eq!(inventory.len(), 3);
+        assert!(!report.is_empty());
+
+        let sword = inventory.get_item(0).unwrap();
+        assert_eq!(
+            sword.get_data::<Durability>().as_deref(),
+            Some(&Durability(7))
+        );
+        let preserved = sword.get_data::<PreservedData>().unwrap();
+        assert_eq!(
+            preserved.tags().collect::<Vec<_>>(),
+            vec!["mod:enchantment"]
+        );
+
+        let wand = inventory.get_item(2).unwrap();
+        assert!(wand.item_type().is_placeholder());
+        assert_eq!(wand.item_type().key(), Some("mod:wand"));
+        assert_eq!(wand.max_stack_size(), 8);
+        assert!(wand.can_stack_with(inventory.get_item(3).unwrap()));
+
+        let json = registry
+            .serde_scope(|| serde_json::to_string(&inventory))
+            .unwrap();
+        assert_eq!(json, SAVE);
+
+        let wands = inventory.remove_item(3).unwrap();
+        assert!(inventory.add_item(wands).is_none());
+        assert_eq!(inventory.get_item(2).unwrap().count(), 5);
+    }
+
+    #[test]
+    fn preserve_missing_types_with_bincode() {
+        let mut old_registry = registry();
+        let wand = old_registry.register("mod:wand", ItemType::with_data(MaxStackSize(8)));
+        let mut inventory = SlotInventory::new(2);
+        inventory.insert_item(1, old_registry[wand].item_with_data(Durability(3)));
+        let bytes = old_registry
+            .serde_scope(|| bincode::serialize(&SaveGame::new(&inventory)))
+            .unwrap();
+
+        let registry = registry();
+        let (save, report) = registry.load_scope(UnknownPolicy::Preserve, || {
+            bincode::deserialize::<SaveGame<SlotInventory>>(&bytes)
+        });
+        let inventory = save.unwrap().into_content();
+        let item: &Item = inventory.get_item(1).unwrap();
+        assert!(item.item_type().is_placeholder());
+        assert_eq!(
+            item.get_data::<Durability>().as_deref(),
+            Some(&Durability(3))
+        );
+        assert_eq!(
+            report.missing_item_types().collect::<Vec<_>>(),
+            vec![("mod:wand", 1)]
+        );
+
+        let resaved = registry
+            .serde_scope(|| bincode::serialize(&SaveGame::new(&inventory)))
+            .unwrap();
+        assert_eq!(resaved, bytes);
+    }
+
+    #[test]
+    fn preserve_schema_versions_in_saves() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Enchantment(String);
+        impl ItemData for Enchantment {}
+
+        let mut modded = registry();
+        modded.register_data_versioned::<Enchantment>("mod:enchantment", 2);
+        let mut inventory = SlotInventory::new(1);
+        let sword = modded.id_of("test:sword").unwrap();
+        inventory.add_item(modded[sword].item_with_data(Enchantment("fire".into())));
+        let json = modded
+            .serde_scope(|| serde_json::to_string(&SaveGame::new(&inventory)))
+            .unwrap();
+
+        let registry = registry();
+        let (save, _) = registry.load_scope(UnknownPolicy::Preserve, || {
+            serde_json::from_str::<SaveGame<SlotInventory>>(&json)
+        });
+        let inventory = save.unwrap().into_content();
+        let preserved = inventory
+            .get_item(0)
+            .unwrap()
+            .get_data::<PreservedData>()
+            .unwrap();
+        assert_eq!(preserved.version("mod:enchantment"), Some(2));
+        let resaved = registry
+            .serde_scope(|| serde_json::to_string(&SaveGame::new(&inventory)))
+            .unwrap();
+        assert!(resaved.contains(r#""mod:enchantment@2":"fire""#));
+        assert!(!resaved.contains(r#""data_versions":{"mod:enchantment""#));
+
+        let inventory = modded
+            .serde_scope(|| serde_json::from_str::<SaveGame<SlotInventory>>(&resaved))
+            .unwrap()
+            .into_content();
+        assert_eq!(
+            inventory
+                .get_item(0)
+                .unwrap()
+                .get_data::<Enchantment>()
+                .as_deref(),
+            Some(&Enchantment("fire".into()))
+        );
+    }
+
+    #[test]
+    fn save_preserved_data_of_different_versions() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Enchantment {
+            name: String,
+            level: u32,
+        }
+        impl ItemData for Enchantment {}
+
+        let save = r#"{"format_version":1,"data_versions":{"mod:enchantment":2},"content":{"slots":[{"type":"test:sword","count":1,"data":{"mod:enchantment":{"level":3,"name":"fire"}}},{"type":"test:sword","count":1,"data":{"mod:enchantment@1":"ice"}}]}}"#;
+        let mut modded = registry();
+        modded.register_data_versioned::<Enchantment>("mod:enchantment", 2);
+        modded.register_migration("mod:enchantment", 1, |name: String| Enchantment {
+            name,
+            level: 1,
+        });
+        let registry = registry();
+        let (inventory, _) = registry.load_scope(UnknownPolicy::Preserve, || {
+            serde_json::from_str::<SaveGame<SlotInventory>>(save)
+        });
+        let inventory = inventory.unwrap().into_content();
+        let version = |slot: usize| {
+            let item = inventory.get_item(slot).unwrap();
+            item.get_data::<PreservedData>()
+                .unwrap()
+                .version("mod:enchantment")
+        };
+        assert_eq!((version(0), version(1)), (Some(2), Some(1)));
+        let resaved = registry
+            .serde_scope(|| serde_json::to_string(&SaveGame::new(&inventory)))
+            .unwrap();
+
+        let inventory = modded
+            .serde_scope(|| serde_json::from_str::<SaveGame<SlotInventory>>(&resaved))
+            .unwrap()
+            .into_content();
+        let enchantment = |slot: usize| {
+            let item = inventory.get_item(slot).unwrap();
+            let enchantment = item.get_data::<Enchantment>().unwrap();
+            (enchantment.name.clone(), enchantment.level)
+        };
+        assert_eq!(enchantment(0), ("fire".to_owned(), 3));
+        assert_eq!(enchantment(1), ("ice".to_owned(), 1));
+    }
+}
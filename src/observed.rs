@@ -0,0 +1,732 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    fmt,
+    hash::Hash,
+    sync::Arc,
+};
+
+use crate::{
+    AddReceipt, Generation, Inventory, InventoryEntries, InventoryItems, InventorySelector, Item,
+    ItemType, ShrubError, TransactionInventory,
+};
+
+/// Something that happened to the items of an `Observed` inventory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryEvent<S> {
+    /// A stack was put into the inventory.
+    ItemAdded {
+        /// Where the stack was put.
+        selector: S,
+        /// The type of the stack.
+        item_type: Arc<ItemType>,
+        /// The count of the stack.
+        count: u32,
+    },
+    /// A stack left the inventory.
+    ItemRemoved {
+        /// Where the stack was.
+        selector: S,
+        /// The type of the stack.
+        item_type: Arc<ItemType>,
+        /// The count of the stack when it left.
+        count: u32,
+    },
+    /// A whole stack was moved to another place in the inventory.
+    ItemMoved {
+        /// Where the stack was.
+        from: S,
+        /// Where the stack is now.
+        to: S,
+    },
+    /// The count of a stack changed, for example because items were merged into it.
+    StackChanged {
+        /// Where the stack is.
+        selector: S,
+        /// The count before the change.
+        old_count: u32,
+        /// The count after the change.
+        new_count: u32,
+    },
+    /// Data was added to, removed from or replaced on an item.
+    DataChanged {
+        /// Where the item is.
+        selector: S,
+    },
+}
+
+type Listener<S> = Box<dyn FnMut(&InventoryEvent<S>) + Send + Sync>;
+
+/// Wraps an inventory and emits an `InventoryEvent` for every change to its items,
+/// to registered listeners and to a queue that can be drained, see `Observed::drain_events`.
+///
+/// Events are found by comparing the items before and after each change, so any inventory that implements
+/// `InventoryEntries` can be observed. Calls that know which selectors they change, like `remove_item` or `get_item_mut`,
+/// only compare the items at these selectors, unless adding or removing items may shift other items of the inventory,
+/// see `InventoryEntries::STABLE_SELECTORS`. Changes made through `get_item_mut`, `items_mut` or `inner_mut` are found
+/// with the next call that takes the Observed as mutable, or with `Observed::flush`.
+/// Data changed in place, like through `Item::get_data_mut`, or replaced by data of the same type is only detected
+/// on items that track their changes, see `Item::track_changes`. On other items only adding and removing data is detected,
+/// and they are compared again by every call that may have changed them.
+///
+/// Every event also advances the generation of the inventory and of the selectors it concerns,
+/// so the places that changed since a `Generation` can be found with `Observed::changed_since`.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{Inventory, InventoryEvent, InventorySelector, ItemType, MaxStackSize, Observed, SlotInventory};
+///
+/// let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+/// let mut quiver = Observed::new(SlotInventory::new(2))
+///     .with_listener(|event| println!("quiver changed: {event:?}"));
+///
+/// quiver.add_item(arrow.item_new());
+/// quiver.add_item(arrow.item_new());
+/// quiver.remove_item(0);
+///
+/// let events: Vec<_> = quiver.drain_events().collect();
+/// assert!(matches!(events[0], InventoryEvent::ItemAdded { selector: 0, count: 1, .. }));
+/// assert!(matches!(events[1], InventoryEvent::StackChanged { selector: 0, old_count: 1, new_count: 2 }));
+/// assert!(matches!(events[2], InventoryEvent::ItemRemoved { selector: 0, count: 2, .. }));
+/// ```
+pub struct Observed<I, S> {
+    inventory: I,
+    /// The items as of the last emitted events.
+    snapshot: HashMap<S, Snapshot>,
+    /// Which items may have changed since the last snapshot.
+    dirty: Dirty<S>,
+    queue: Option<VecDeque<InventoryEvent<S>>>,
+    listeners: Vec<Listener<S>>,
+    /// The generation of the latest event.
+    generation: Generation,
+    /// The generation of the latest event at each selector.
+    changes: HashMap<S, Generation>,
+}
+
+/// The items of an `Observed` that may have changed since the last snapshot.
+enum Dirty<S> {
+    Clean,
+    Selectors(Vec<S>),
+    All,
+}
+
+/// The state of an item as far as events are concerned.
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    item_type: Arc<ItemType>,
+    count: u32,
+    /// The generation of the item, which tells it apart from other items and changes with its count and data.
+    generation: Option<Generation>,
+    /// The generation of the latest change to the item's own data.
+    data: Option<Generation>,
+    /// The types of the item's own data, when it doesn't track its changes.
+    data_types: Vec<TypeId>,
+}
+
+impl Snapshot {
+    fn of(item: &Item) -> Self {
+        let mut data_types = Vec::new();
+        if !item.is_tracking_changes() {
+            data_types.extend(item.data.keys().copied());
+            data_types.sort_unstable();
+        }
+        Snapshot {
+            item_type: item.item_type().clone(),
+            count: item.count(),
+            generation: item.generation(),
+            data: item.latest_data_generation(),
+            data_types,
+        }
+    }
+
+    /// Returns `true` when the item is the one of the snapshot and didn't change since.
+    fn is_current(&self, item: &Item) -> bool {
+        self.generation.is_some() && self.generation == item.generation()
+    }
+}
+
+impl<I, S> Observed<I, S>
+where
+    I: InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    /// Wraps the inventory. Items already in it don't cause any events.
+    pub fn new(inventory: I) -> Self {
+        let snapshot = inventory
+            .entries()
+            .map(|(selector, item)| (selector, Snapshot::of(item)))
+            .collect();
+        Observed {
+            inventory,
+            snapshot,
+            dirty: Dirty::Clean,
+            queue: Some(VecDeque::new()),
+            listeners: Vec::new(),
+            generation: Generation::ZERO,
+            changes: HashMap::new(),
+        }
+    }
+
+    /// Adds a listener and returns the Observed, see `Observed::add_listener`.
+    pub fn with_listener(
+        mut self,
+        listener: impl FnMut(&InventoryEvent<S>) + Send + Sync + 'static,
+    ) -> Self {
+        self.add_listener(listener);
+        self
+    }
+
+    /// Adds a listener, which is called with every event as soon as it happens.
+    pub fn add_listener(
+        &mut self,
+        listener: impl FnMut(&InventoryEvent<S>) + Send + Sync + 'static,
+    ) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Stops queueing events and returns the Observed, for when they are only handled by listeners.
+    pub fn without_queue(mut self) -> Self {
+        self.queue = None;
+        self
+    }
+
+    /// Takes the queued events out of the queue, oldest first.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = InventoryEvent<S>> + '_ {
+        self.flush();
+        self.queue.iter_mut().flat_map(|queue| queue.drain(..))
+    }
+
+    /// Returns the generation of the latest event, or `Generation::ZERO` when there wasn't any yet.
+    pub fn generation(&mut self) -> Generation {
+        self.flush();
+        self.generation
+    }
+
+    /// Iterates over the selectors at which something happened after the given generation,
+    /// like the slots to render again or to send over the network.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, InventorySelector, ItemData, ItemType, Observed, SlotInventory};
+    ///
+    /// struct Durability(u32);
+    /// impl ItemData for Durability {}
+    ///
+    /// let sword = Arc::new(ItemType::new());
+    /// let mut inventory = Observed::new(SlotInventory::new(4));
+    /// for _ in 0..3 {
+    ///     let mut item = sword.item_with_data(Durability(100));
+    ///     item.track_changes();
+    ///     inventory.add_item(item);
+    /// }
+    ///
+    /// let rendered = inventory.generation();
+    /// inventory.get_item_mut(1).unwrap().get_data_mut::<Durability>().unwrap().0 -= 1;
+    /// inventory.remove_item(2);
+    ///
+    /// let mut changed: Vec<usize> = inventory.changed_since(rendered).copied().collect();
+    /// changed.sort();
+    /// assert_eq!(changed, vec![1, 2]);
+    /// ```
+    pub fn changed_since(&mut self, generation: Generation) -> impl Iterator<Item = &S> {
+        self.flush();
+        self.changes
+            .iter()
+            .filter(move |(_, changed)| **changed > generation)
+            .map(|(selector, _)| selector)
+    }
+
+    /// Borrows the wrapped inventory.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.inventory
+    }
+
+    /// Borrows the wrapped inventory as mutable. Its changes cause events with the next call that takes the Observed as mutable.
+    pub fn inner_mut(&mut self) -> &mut I {
+        self.flush();
+        self.dirty = Dirty::All;
+        &mut self.inventory
+    }
+
+    /// Unwraps the inventory. Changes that weren't flushed yet don't cause events anymore.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.inventory
+    }
+
+    /// Emits the events of changes made through `get_item_mut`, `items_mut` or `inner_mut`.
+    /// This happens automatically with the next call that takes the Observed as mutable.
+    pub fn flush(&mut self) {
+        let selectors = match std::mem::replace(&mut self.dirty, Dirty::Clean) {
+            Dirty::Clean => return,
+            Dirty::Selectors(selectors) => selectors,
+            Dirty::All => self.changed_selectors(),
+        };
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for selector in selectors {
+            let current = self.inventory.get_item(selector.clone()).map(Snapshot::of);
+            let old = match &current {
+                Some(current) => self.snapshot.insert(selector.clone(), current.clone()),
+                None => self.snapshot.remove(&selector),
+            };
+            match (old, current) {
+                (Some(old), Some(current)) if old.item_type == current.item_type => {
+                    if old.count != current.count {
+                        changed.push(InventoryEvent::StackChanged {
+                            selector: selector.clone(),
+                            old_count: old.count,
+                            new_count: current.count,
+                        });
+                    }
+                    if old.data != current.data || old.data_types != current.data_types {
+                        changed.push(InventoryEvent::DataChanged {
+                            selector: selector.clone(),
+                        });
+                    }
+                }
+                (old, current) => {
+                    removed.extend(old.map(|old| (selector.clone(), old)));
+                    added.extend(current.map(|current| (selector, current)));
+                }
+            }
+        }
+
+        // a stack that left one place and showed up unchanged at another one was moved
+        let mut moved = Vec::new();
+        removed.retain(|(from, old)| {
+            let Some(index) = added.iter().position(|(_, new)| new == old) else {
+                return true;
+            };
+            let (to, _) = added.swap_remove(index);
+            moved.push(InventoryEvent::ItemMoved {
+                from: from.clone(),
+                to,
+            });
+            false
+        });
+
+        let removed = removed
+            .into_iter()
+            .map(|(selector, old)| InventoryEvent::ItemRemoved {
+                selector,
+                item_type: old.item_type,
+                count: old.count,
+            });
+        let added = added
+            .into_iter()
+            .map(|(selector, new)| InventoryEvent::ItemAdded {
+                selector,
+                item_type: new.item_type,
+                count: new.count,
+            });
+        let events: Vec<_> = removed.chain(moved).chain(added).chain(changed).collect();
+        for event in events {
+            self.emit(event);
+        }
+    }
+
+    /// Collects the selectors whose items changed, showed up or left since the last snapshot.
+    fn changed_selectors(&self) -> Vec<S> {
+        let mut selectors = Vec::new();
+        let mut kept = 0;
+        for (selector, item) in self.inventory.entries() {
+            match self.snapshot.get(&selector) {
+                Some(old) => {
+                    kept += 1;
+                    if !old.is_current(item) {
+                        selectors.push(selector);
+                    }
+                }
+                None => selectors.push(selector),
+            }
+        }
+        if kept < self.snapshot.len() {
+            let left = self
+                .snapshot
+                .keys()
+                .filter(|selector| self.inventory.get_item((*selector).clone()).is_none());
+            selectors.extend(left.cloned());
+        }
+        selectors
+    }
+
+    fn emit(&mut self, event: InventoryEvent<S>) {
+        self.generation = Generation::next();
+        let selectors = match &event {
+            InventoryEvent::ItemMoved { from, to } => [Some(from), Some(to)],
+            InventoryEvent::ItemAdded { selector, .. }
+            | InventoryEvent::ItemRemoved { selector, .. }
+            | InventoryEvent::StackChanged { selector, .. }
+            | InventoryEvent::DataChanged { selector } => [Some(selector), None],
+        };
+        for selector in selectors.into_iter().flatten() {
+            self.changes.insert(selector.clone(), self.generation);
+        }
+        for listener in &mut self.listeners {
+            listener(&event);
+        }
+        if let Some(queue) = &mut self.queue {
+            queue.push_back(event);
+        }
+    }
+
+    /// Returns which items to compare after a change at the selectors, which are all items when other items may have shifted.
+    fn dirty_at(selectors: Vec<S>) -> Dirty<S> {
+        if I::STABLE_SELECTORS {
+            Dirty::Selectors(selectors)
+        } else {
+            Dirty::All
+        }
+    }
+
+    /// Changes the inventory with `f` and emits the events of the change at the items `dirty` tells for its result.
+    fn observe<R>(&mut self, f: impl FnOnce(&mut I) -> R, dirty: impl FnOnce(&R) -> Dirty<S>) -> R {
+        self.flush();
+        let result = f(&mut self.inventory);
+        self.dirty = dirty(&result);
+        self.flush();
+        result
+    }
+}
+
+impl<I, S> Inventory for Observed<I, S>
+where
+    I: Inventory + InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    fn add_item(&mut self, item: Item) -> Option<Item> {
+        self.observe(|inventory| inventory.add_item(item), |_| Dirty::All)
+    }
+
+    fn try_add_item(&mut self, item: Item) -> Result<(), ShrubError> {
+        self.observe(|inventory| inventory.try_add_item(item), |_| Dirty::All)
+    }
+}
+
+impl<I, S> InventorySelector<S> for Observed<I, S>
+where
+    I: InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    fn get_item(&self, selector: S) -> Option<&Item> {
+        self.inventory.get_item(selector)
+    }
+
+    /// Borrows the item as mutable. Its changes cause events with the next call that takes the Observed as mutable.
+    fn get_item_mut(&mut self, selector: S) -> Option<&mut Item> {
+        self.flush();
+        self.dirty = Dirty::Selectors(vec![selector.clone()]);
+        self.inventory.get_item_mut(selector)
+    }
+
+    fn remove_item(&mut self, selector: S) -> Option<Item> {
+        let dirty = Self::dirty_at(vec![selector.clone()]);
+        self.observe(|inventory| inventory.remove_item(selector), |_| dirty)
+    }
+}
+
+impl<I, S> InventoryItems for Observed<I, S>
+where
+    I: InventoryItems + InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.inventory.items()
+    }
+
+    /// Iterates mutably over all items. Their changes cause events with the next call that takes the Observed as mutable.
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.flush();
+        self.dirty = Dirty::All;
+        self.inventory.items_mut()
+    }
+}
+
+impl<I, S> InventoryEntries<S> for Observed<I, S>
+where
+    I: InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    const STABLE_SELECTORS: bool = I::STABLE_SELECTORS;
+
+    fn entries(&self) -> impl Iterator<Item = (S, &Item)> {
+        self.inventory.entries()
+    }
+}
+
+impl<I, S> TransactionInventory<S> for Observed<I, S>
+where
+    I: TransactionInventory<S> + InventoryEntries<S>,
+    S: Clone + Eq + Hash,
+{
+    fn add_item_tracked(&mut self, item: Item) -> Result<AddReceipt<S>, ShrubError> {
+        self.observe(
+            |inventory| inventory.add_item_tracked(item),
+            |result| match result {
+                Ok(receipt) => Self::dirty_at(receipt.selectors().cloned().collect()),
+                Err(_) => Dirty::Clean,
+            },
+        )
+    }
+
+    fn insert_item(&mut self, selector: S, item: Item) -> Option<Item> {
+        let dirty = Self::dirty_at(vec![selector.clone()]);
+        self.observe(|inventory| inventory.insert_item(selector, item), |_| dirty)
+    }
+}
+
+impl<I: fmt::Debug, S> fmt::Debug for Observed<I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observed")
+            .field("inventory", &self.inventory)
+            .field("queued", &self.queue.as_ref().map(VecDeque::len))
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        Generation, Inventory, InventoryEntries, InventoryEvent, InventoryItems, InventorySelector,
+        Item, ItemData, ItemType, MaxStackSize, Observed, SlotInventory, TransactionInventory,
+        Weight,
+    };
+
+    /// Keeps its items without gaps, so removing an item shifts the ones after it.
+    struct Pile(Vec<Item>);
+
+    impl InventorySelector<usize> for Pile {
+        fn get_item(&self, selector: usize) -> Option<&Item> {
+            self.0.get(selector)
+        }
+
+        fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
+            self.0.get_mut(selector)
+        }
+
+        fn remove_item(&mut self, selector: usize) -> Option<Item> {
+            (selector < self.0.len()).then(|| self.0.remove(selector))
+        }
+    }
+
+    impl InventoryEntries<usize> for Pile {
+        fn entries(&self) -> impl Iterator<Item = (usize, &Item)> {
+            self.0.iter().enumerate()
+        }
+    }
+
+    #[test]
+    fn emit_events_for_added_merged_and_removed_items() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut quiver = Observed::new(SlotInventory::new(2));
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(10);
+        quiver.add_item(arrows);
+        let mut arrows = arrow.item_new();
+        arrows.set_count(10);
+        quiver.add_item(arrows);
+        quiver.remove_item(1);
+
+        let events: Vec<_> = quiver.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                InventoryEvent::ItemAdded {
+                    selector: 0,
+                    item_type: arrow.clone(),
+                    count: 10
+                },
+                InventoryEvent::ItemAdded {
+                    selector: 1,
+                    item_type: arrow.clone(),
+                    count: 4
+                },
+                InventoryEvent::StackChanged {
+                    selector: 0,
+                    old_count: 10,
+                    new_count: 16
+                },
+                InventoryEvent::ItemRemoved {
+                    selector: 1,
+                    item_type: arrow.clone(),
+                    count: 4
+                },
+            ]
+        );
+        assert_eq!(quiver.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn detect_moves_and_changes_through_mutable_borrows() {
+        let sword = Arc::new(ItemType::with_data(Weight(3.0)));
+        let mut inventory = SlotInventory::new(3);
+        inventory.add_item(sword.item_new());
+        let mut inventory = Observed::new(inventory);
+
+        let inner = inventory.inner_mut();
+        let item = inner.remove_item(0).unwrap();
+        inner.insert_item(2, item);
+        inventory.get_item_mut(2).unwrap().add_data(Weight(4.0));
+        inventory.get_item_mut(2).unwrap().set_count(1);
+
+        let events: Vec<_> = inventory.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                InventoryEvent::ItemMoved { from: 0, to: 2 },
+                InventoryEvent::DataChanged { selector: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn call_listeners_without_queueing() {
+        let stone = Arc::new(ItemType::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut inventory = Observed::new(SlotInventory::new(2)).without_queue();
+        let log = seen.clone();
+        inventory.add_listener(move |event| log.lock().unwrap().push(event.clone()));
+
+        inventory.add_item(stone.item_new());
+        assert_eq!(seen.lock().unwrap().len(), 1);
+        assert!(inventory.insert_item(0, stone.item_new()).is_some());
+        inventory.remove_item(0);
+
+        assert_eq!(seen.lock().unwrap().len(), 2);
+        assert!(matches!(
+            seen.lock().unwrap()[1],
+            InventoryEvent::ItemRemoved { selector: 0, .. }
+        ));
+        assert_eq!(inventory.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn detect_data_changed_in_place_on_tracked_items() {
+        struct Durability(u32);
+        impl ItemData for Durability {}
+
+        let sword = Arc::new(ItemType::new());
+        let mut inventory = Observed::new(SlotInventory::new(2));
+        inventory.add_item(sword.item_with_data(Durability(100)));
+        let mut tracked = sword.item_with_data(Durability(100));
+        tracked.track_changes();
+        inventory.add_item(tracked);
+        assert_eq!(inventory.drain_events().count(), 2);
+
+        let added = inventory.generation();
+        assert!(added > Generation::ZERO);
+        for slot in 0..2 {
+            let item = inventory.get_item_mut(slot).unwrap();
+            item.get_data_mut::<Durability>().unwrap().0 -= 1;
+        }
+
+        let events: Vec<_> = inventory.drain_events().collect();
+        assert_eq!(events, [InventoryEvent::DataChanged { selector: 1 }]);
+        assert_eq!(inventory.changed_since(added).collect::<Vec<_>>(), [&1]);
+        assert!(inventory.generation() > added);
+        let changed = inventory.generation();
+        assert_eq!(inventory.changed_since(changed).count(), 0);
+        assert!(!inventory.remove_item(0).unwrap().is_tracking_changes());
+    }
+
+    #[test]
+    fn detect_replaced_data_without_size() {
+        struct Enchanted;
+        impl ItemData for Enchanted {}
+
+        let sword = Arc::new(ItemType::new());
+        let mut inventory = Observed::new(SlotInventory::new(2));
+        for _ in 0..2 {
+            let mut item = sword.item_with_data(Enchanted);
+            item.track_changes();
+            inventory.add_item(item);
+        }
+        assert_eq!(inventory.drain_events().count(), 2);
+
+        inventory.get_item_mut(1).unwrap().add_data(Enchanted);
+        let events: Vec<_> = inventory.drain_events().collect();
+        assert_eq!(events, [InventoryEvent::DataChanged { selector: 1 }]);
+
+        for item in inventory.items_mut() {
+            item.add_data(Enchanted);
+        }
+        let events: Vec<_> = inventory.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                InventoryEvent::DataChanged { selector: 0 },
+                InventoryEvent::DataChanged { selector: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn emit_events_at_the_stacks_of_tracked_adds() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut arrows = arrow.item_new();
+        arrows.set_count(10);
+        let mut quiver = Observed::new(SlotInventory::new(2));
+        quiver.insert_item(1, arrows);
+        assert_eq!(quiver.drain_events().count(), 1);
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(8);
+        quiver.add_item_tracked(arrows).unwrap();
+        let mut arrows = arrow.item_new();
+        arrows.set_count(40);
+        assert!(quiver.add_item_tracked(arrows).is_err());
+
+        let events: Vec<_> = quiver.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                InventoryEvent::ItemAdded {
+                    selector: 0,
+                    item_type: arrow.clone(),
+                    count: 2
+                },
+                InventoryEvent::StackChanged {
+                    selector: 1,
+                    old_count: 10,
+                    new_count: 16
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_all_items_of_inventories_with_shifting_selectors() {
+        let [stone, dirt, sand] = [(); 3].map(|_| Arc::new(ItemType::new()));
+        let items = [&stone, &dirt, &sand].map(|item_type| item_type.item_new());
+        let mut pile = Observed::new(Pile(Vec::from(items)));
+
+        pile.get_item_mut(2).unwrap().add_data(Weight(1.0));
+        assert_eq!(
+            pile.drain_events().collect::<Vec<_>>(),
+            [InventoryEvent::DataChanged { selector: 2 }]
+        );
+        pile.remove_item(0);
+        let events: Vec<_> = pile.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                InventoryEvent::ItemRemoved {
+                    selector: 0,
+                    item_type: stone.clone(),
+                    count: 1
+                },
+                InventoryEvent::ItemMoved { from: 1, to: 0 },
+                InventoryEvent::ItemMoved { from: 2, to: 1 },
+            ]
+        );
+    }
+}
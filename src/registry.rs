@@ -0,0 +1,534 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Index,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+#[cfg(feature = "serde")]
+use crate::serialization::ItemDataTags;
+use crate::{itemtype::tag_key, ItemType};
+
+/// Compact numeric id of an `ItemType` registered in an `ItemRegistry`.
+/// Ids are handed out in registration order and stay stable for the lifetime of the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemTypeId(u32);
+
+impl ItemTypeId {
+    /// Returns the numeric value of the id.
+    #[inline]
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// Tells `ItemRegistry`s apart, so ItemTypes of different registries never compare equal by their ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RegistryIdentity(u64);
+
+impl RegistryIdentity {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        RegistryIdentity(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// An ItemRegistry owns all `ItemType`s of a game and identifies them by a namespaced key like `core:wooden_sword`.
+///
+/// # Examples
+/// ```
+/// use shrub::{ItemRegistry, ItemType};
+///
+/// let mut registry = ItemRegistry::new();
+/// let sword = registry.register("core:wooden_sword", ItemType::new());
+/// assert_eq!(registry.id_of("core:wooden_sword"), Some(sword));
+///
+/// let item = registry[sword].item_new();
+/// assert_eq!(item.item_type_id(), Some(sword));
+/// ```
+#[derive(Debug)]
+pub struct ItemRegistry {
+    identity: RegistryIdentity,
+    types: Vec<Arc<ItemType>>,
+    ids: HashMap<Box<str>, ItemTypeId>,
+    /// The tags or groups every tag group includes.
+    tag_groups: HashMap<Box<str>, BTreeSet<Box<str>>>,
+    #[cfg(feature = "serde")]
+    pub(crate) data_tags: ItemDataTags,
+}
+
+impl Default for ItemRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ItemRegistry {
+    /// Creates a new ItemRegistry without any ItemTypes.
+    pub fn new() -> Self {
+        ItemRegistry {
+            identity: RegistryIdentity::next(),
+            types: Vec::new(),
+            ids: HashMap::new(),
+            tag_groups: HashMap::new(),
+            #[cfg(feature = "serde")]
+            data_tags: ItemDataTags::new(),
+        }
+    }
+
+    /// Registers the ItemType under the given key and returns its new id.
+    ///
+    /// Keys consist of a namespace and a name separated by a colon, like `core:wooden_sword`.
+    /// Both parts may only contain lowercase ascii letters, digits and `_`, `-`, `.` or `/`.
+    ///
+    /// # Panics
+    /// Panics when the key is malformed or already registered.
+    pub fn register(&mut self, key: &str, mut item_type: ItemType) -> ItemTypeId {
+        assert!(is_valid_key(key), "malformed item type key `{key}`");
+        assert!(
+            !self.ids.contains_key(key),
+            "item type `{key}` is already registered"
+        );
+        let id = ItemTypeId(self.types.len() as u32);
+        item_type.register(self.identity, id, key);
+        self.inherit_tags(&mut item_type);
+        self.types.push(Arc::new(item_type));
+        self.ids.insert(key.into(), id);
+        id
+    }
+
+    /// Puts the tags, parent and data of the ItemType in place of the ones registered with the given id.
+    ///
+    /// The registered ItemType is changed in place and gets the tags of matching tag groups like `register` does,
+    /// so existing `Item`s of the type, ItemTypes deriving from it and other `Arc`s of it see the new definition right away.
+    /// Data borrowed from the previous definition stays readable, and the previous definition is dropped with the last `DataRef`.
+    ///
+    /// # Panics
+    /// Panics when no ItemType with this id is registered, or the new ItemType derives from the one it replaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, ItemType, MaxStackSize};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// let arrow = registry.register("core:arrow", ItemType::with_data(MaxStackSize(16)));
+    /// let item = registry[arrow].item_new();
+    ///
+    /// registry.replace(arrow, ItemType::with_data(MaxStackSize(64)));
+    /// assert_eq!(item.max_stack_size(), 64);
+    /// assert_eq!(registry.key_of(arrow), Some("core:arrow"));
+    /// ```
+    pub fn replace(&mut self, id: ItemTypeId, item_type: ItemType) {
+        let item_type = self.prepare_replacement(id, item_type);
+        self.redefine_in_place(id, item_type);
+    }
+
+    /// Checks the ItemType which will replace the one with the given id, and gives it the id, key and tags of matching groups.
+    pub(crate) fn prepare_replacement(&self, id: ItemTypeId, mut item_type: ItemType) -> ItemType {
+        let replaced = &**self.get(id).expect("item type id is not registered");
+        let key: Box<str> = replaced.key().unwrap().into();
+        assert!(
+            std::iter::successors(item_type.parent(), |ancestor| ancestor.parent())
+                .all(|ancestor| !std::ptr::eq(&*ancestor, replaced)),
+            "item type `{key}` can't derive from itself"
+        );
+        item_type.register(self.identity, id, &key);
+        self.inherit_tags(&mut item_type);
+        item_type
+    }
+
+    /// Puts the definition of the prepared ItemType in place of the one registered with the given id.
+    pub(crate) fn redefine_in_place(&mut self, id: ItemTypeId, item_type: ItemType) {
+        self.types[id.0 as usize].redefine(item_type.into_definition());
+    }
+
+    /// Adds the members, which are tags or other groups, to the tag group.
+    /// Registered ItemTypes with any of the members get the tag of the group, and so do ItemTypes registered afterwards.
+    ///
+    /// # Examples
+    /// ```
+    /// use shrub::{ItemRegistry, ItemType};
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// registry.add_tag_group("core:logs", ["core:oak_logs", "core:birch_logs"]);
+    /// let oak_log = registry.register("core:oak_log", ItemType::new().with_tag("core:oak_logs"));
+    /// registry.add_tag_group("core:burnable", ["#core:logs"]);
+    ///
+    /// assert!(registry[oak_log].has_tag("core:logs"));
+    /// assert!(registry[oak_log].has_tag("core:burnable"));
+    /// assert_eq!(registry.tagged("core:burnable").count(), 1);
+    /// ```
+    pub fn add_tag_group<'a>(&mut self, group: &str, members: impl IntoIterator<Item = &'a str>) {
+        self.tag_groups
+            .entry(tag_key(group).into())
+            .or_default()
+            .extend(members.into_iter().map(|member| tag_key(member).into()));
+        for item_type in &self.types {
+            let inherited = self.inherited_tags(item_type);
+            if !inherited.is_empty() {
+                item_type.add_group_tags(inherited);
+            }
+        }
+    }
+
+    /// Iterates over the direct members of the tag group.
+    pub fn tag_group(&self, group: &str) -> impl Iterator<Item = &str> + '_ {
+        self.tag_groups
+            .get(tag_key(group))
+            .into_iter()
+            .flatten()
+            .map(|member| &**member)
+    }
+
+    /// Iterates over all registered ItemTypes with the tag in registration order.
+    pub fn tagged<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = (ItemTypeId, &'a Arc<ItemType>)> + 'a {
+        self.iter()
+            .filter(move |(_, item_type)| item_type.has_tag(tag))
+    }
+
+    /// Gives the ItemType the tags of all groups that include one of its tags, directly or through other groups.
+    fn inherit_tags(&self, item_type: &mut ItemType) {
+        for tag in self.inherited_tags(item_type) {
+            item_type.add_tag(&tag);
+        }
+    }
+
+    /// Collects the tags of all groups the ItemType doesn't have yet, but which include one of its tags.
+    fn inherited_tags(&self, item_type: &ItemType) -> BTreeSet<Box<str>> {
+        let mut inherited = BTreeSet::new();
+        loop {
+            let has_tag = |tag: &str| inherited.contains(tag) || item_type.has_tag(tag);
+            let groups: Vec<&Box<str>> = self
+                .tag_groups
+                .iter()
+                .filter(|(group, members)| {
+                    !has_tag(group) && members.iter().any(|tag| has_tag(tag))
+                })
+                .map(|(group, _)| group)
+                .collect();
+            if groups.is_empty() {
+                return inherited;
+            }
+            inherited.extend(groups.into_iter().cloned());
+        }
+    }
+
+    /// Borrows the ItemType with the given id.
+    #[inline]
+    pub fn get(&self, id: ItemTypeId) -> Option<&Arc<ItemType>> {
+        self.types.get(id.0 as usize)
+    }
+
+    /// Borrows the ItemType registered under the given key.
+    #[inline]
+    pub fn get_by_key(&self, key: &str) -> Option<&Arc<ItemType>> {
+        self.get(self.id_of(key)?)
+    }
+
+    /// Returns the id of the ItemType registered under the given key.
+    #[inline]
+    pub fn id_of(&self, key: &str) -> Option<ItemTypeId> {
+        self.ids.get(key).copied()
+    }
+
+    /// Returns the key the ItemType with the given id was registered under.
+    #[inline]
+    pub fn key_of(&self, id: ItemTypeId) -> Option<&str> {
+        self.get(id)?.key()
+    }
+
+    /// Returns `true` when an ItemType is registered under the given key.
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.ids.contains_key(key)
+    }
+
+    /// Returns the amount of registered ItemTypes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Returns `true` when no ItemType is registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Iterates over all registered ItemTypes in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = (ItemTypeId, &Arc<ItemType>)> + '_ {
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(index, item_type)| (ItemTypeId(index as u32), item_type))
+    }
+}
+
+impl Index<ItemTypeId> for ItemRegistry {
+    type Output = Arc<ItemType>;
+
+    /// Borrows the ItemType with the given id.
+    ///
+    /// # Panics
+    /// Panics when no ItemType with this id is registered.
+    fn index(&self, id: ItemTypeId) -> &Self::Output {
+        self.get(id).expect("item type id is not registered")
+    }
+}
+
+/// Checks that the key has the form `namespace:name`.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    let is_valid_part = |part: &str| {
+        !part.is_empty()
+            && part.bytes().all(|b| {
+                b.is_ascii_lowercase()
+                    || b.is_ascii_digit()
+                    || matches!(b, b'_' | b'-' | b'.' | b'/')
+            })
+    };
+    match key.split_once(':') {
+        Some((namespace, name)) => is_valid_part(namespace) && is_valid_part(name),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{InventorySelector, Item, ItemData, ItemRegistry, ItemType, SlotInventory};
+
+    struct NumberData(u32);
+    impl ItemData for NumberData {}
+
+    #[test]
+    fn register_item_types() {
+        let mut registry = ItemRegistry::new();
+        assert!(registry.is_empty());
+        let sword = registry.register("core:wooden_sword", ItemType::new());
+        let apple = registry.register("core:apple", ItemType::with_data(NumberData(4)));
+
+        assert_eq!(registry.len(), 2);
+        assert_ne!(sword, apple);
+        assert_eq!(registry.id_of("core:apple"), Some(apple));
+        assert_eq!(registry.key_of(sword), Some("core:wooden_sword"));
+        assert_eq!(registry[apple].id(), Some(apple));
+        assert_eq!(
+            registry
+                .get_by_key("core:apple")
+                .unwrap()
+                .get_data::<NumberData>()
+                .unwrap()
+                .0,
+            4
+        );
+        assert!(registry.get_by_key("core:stone").is_none());
+        assert_eq!(
+            registry.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![sword, apple]
+        );
+    }
+
+    #[test]
+    fn item_types_of_different_registries_differ() {
+        let mut armory = ItemRegistry::new();
+        let mut pantry = ItemRegistry::new();
+        let sword = armory.register("a:sword", ItemType::new());
+        let apple = pantry.register("b:apple", ItemType::new());
+        assert_eq!(sword, apple);
+
+        assert_ne!(armory[sword], pantry[apple]);
+        assert!(!armory[sword]
+            .item_new()
+            .can_stack_with(&pantry[apple].item_new()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn register_duplicate_key() {
+        let mut registry = ItemRegistry::new();
+        registry.register("core:apple", ItemType::new());
+        registry.register("core:apple", ItemType::new());
+    }
+
+    #[test]
+    fn reject_malformed_keys() {
+        for key in [
+            "apple",
+            "core:",
+            ":apple",
+            "Core:apple",
+            "core:apple:red",
+            "core:red apple",
+        ] {
+            let result = std::panic::catch_unwind(|| {
+                ItemRegistry::new().register(key, ItemType::new());
+            });
+            assert!(result.is_err(), "`{key}` should be rejected");
+        }
+    }
+
+    #[test]
+    fn items_compare_types_by_id() {
+        let mut registry = ItemRegistry::new();
+        let apple = registry.register("core:apple", ItemType::new());
+        let stone = registry.register("core:stone", ItemType::new());
+        let item = registry[apple].item_new();
+
+        assert_eq!(item.item_type_id(), Some(apple));
+        assert!(item.item_type() == &registry[apple]);
+        assert!(item.item_type() != &registry[stone]);
+    }
+
+    #[test]
+    fn inherit_tags_through_groups() {
+        let mut registry = ItemRegistry::new();
+        registry.add_tag_group("core:fuel", ["core:logs", "core:coal"]);
+        registry.add_tag_group("core:logs", ["core:oak_logs"]);
+        registry.add_tag_group("core:logs", ["#core:birch_logs"]);
+        let oak_log = registry.register("core:oak_log", ItemType::new().with_tag("core:oak_logs"));
+        let stone = registry.register("core:stone", ItemType::new().with_tag("core:rocks"));
+
+        assert_eq!(
+            registry[oak_log].tags().collect::<Vec<_>>(),
+            vec!["core:fuel", "core:logs", "core:oak_logs"]
+        );
+        assert!(!registry[stone].any_of(["core:fuel", "core:logs"]));
+        assert_eq!(
+            registry.tag_group("#core:logs").collect::<Vec<_>>(),
+            vec!["core:birch_logs", "core:oak_logs"]
+        );
+        let fuel: Vec<_> = registry.tagged("core:fuel").map(|(id, _)| id).collect();
+        assert_eq!(fuel, vec![oak_log]);
+    }
+
+    #[test]
+    fn apply_tag_groups_added_after_registering() {
+        let mut registry = ItemRegistry::new();
+        let oak_log = registry.register("core:oak_log", ItemType::new().with_tag("core:oak_logs"));
+        let oak_plank = registry.register(
+            "core:oak_plank",
+            ItemType::new().with_parent(registry[oak_log].clone()),
+        );
+        let stone = registry.register("core:stone", ItemType::new().with_tag("core:rocks"));
+        let item = registry[oak_log].item_new();
+
+        registry.add_tag_group("core:fuel", ["core:logs"]);
+        assert!(!registry[oak_log].has_tag("core:fuel"));
+        registry.add_tag_group("core:logs", ["core:oak_logs"]);
+
+        assert_eq!(
+            registry[oak_log].tags().collect::<Vec<_>>(),
+            vec!["core:fuel", "core:logs", "core:oak_logs"]
+        );
+        assert!(item.item_type().has_tag("core:fuel"));
+        assert!(registry[oak_plank].has_tag("core:logs"));
+        assert!(!registry[stone].any_of(["core:fuel", "core:logs"]));
+        let fuel: Vec<_> = registry.tagged("core:fuel").map(|(id, _)| id).collect();
+        assert_eq!(fuel, vec![oak_log, oak_plank]);
+    }
+
+    #[test]
+    fn apply_tag_groups_to_shared_item_types() {
+        let mut registry = ItemRegistry::new();
+        let oak_log = registry.register(
+            "core:oak_log",
+            ItemType::with_data(NumberData(1)).with_tag("core:oak_logs"),
+        );
+        let item = registry[oak_log].item_new();
+        let data = item.get_data::<NumberData>().unwrap();
+
+        for group in ["core:logs", "core:fuel", "core:wood"] {
+            registry.add_tag_group(group, ["core:oak_logs"]);
+        }
+        assert!(item
+            .item_type()
+            .all_of(["core:logs", "core:fuel", "core:wood"]));
+        assert_eq!(data.0, 1);
+        // only the current definition and the borrowed data share the data, the previous definitions are gone
+        assert_eq!(Arc::strong_count(&registry[oak_log].definition().data), 2);
+    }
+
+    #[test]
+    fn replace_item_types() {
+        let mut registry = ItemRegistry::new();
+        registry.add_tag_group("core:food", ["core:fruit"]);
+        let apple = registry.register("core:apple", ItemType::with_data(NumberData(1)));
+        let red_apple = registry.register(
+            "core:red_apple",
+            ItemType::new().with_parent(registry[apple].clone()),
+        );
+        let item = registry[red_apple].item_new();
+        let handle = registry[apple].clone();
+        let old_data = handle.get_data::<NumberData>().unwrap();
+
+        registry.replace(
+            apple,
+            ItemType::with_data(NumberData(2)).with_tag("core:fruit"),
+        );
+        assert_eq!(old_data.0, 1);
+        assert!(Arc::ptr_eq(&handle, &registry[apple]));
+        assert_eq!(handle.get_data::<NumberData>().unwrap().0, 2);
+        assert_eq!(registry[apple].id(), Some(apple));
+        assert_eq!(registry[apple].key(), Some("core:apple"));
+        assert!(registry[apple].has_tag("core:food"));
+        assert_eq!(item.get_data::<NumberData>().unwrap().0, 2);
+        assert!(item.item_type().has_tag("core:fruit"));
+    }
+
+    #[test]
+    fn drop_replaced_definitions_with_their_last_reader() {
+        struct Token(Arc<()>);
+        impl ItemData for Token {}
+
+        let token = Arc::new(());
+        let mut registry = ItemRegistry::new();
+        let apple = registry.register("core:apple", ItemType::with_data(Token(token.clone())));
+        let item = registry[apple].item_new();
+        let old_data = item.get_data::<Token>().unwrap();
+
+        registry.replace(apple, ItemType::new());
+        assert!(Arc::ptr_eq(&old_data.0, &token));
+        assert_eq!(Arc::strong_count(&token), 2);
+        drop(old_data);
+        assert_eq!(Arc::strong_count(&token), 1);
+        assert!(item.get_data::<Token>().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn replace_with_cycle() {
+        let mut registry = ItemRegistry::new();
+        let apple = registry.register("core:apple", ItemType::new());
+        let red_apple = registry.register(
+            "core:red_apple",
+            ItemType::new().with_parent(registry[apple].clone()),
+        );
+        let parent = registry[red_apple].clone();
+        registry.replace(apple, ItemType::new().with_parent(parent));
+    }
+
+    #[test]
+    fn inventories_are_static_and_send() {
+        fn assert_static_send<T: Send + Sync + 'static>(_: &T) {}
+
+        let mut registry = ItemRegistry::new();
+        let apple = registry.register("core:apple", ItemType::new());
+        let mut inventory = SlotInventory::new(1);
+        inventory.insert_item(0, registry[apple].item_new());
+        assert_static_send(&inventory);
+
+        let registry = Arc::new(registry);
+        let handle = std::thread::spawn(move || {
+            let item: Item = inventory.remove_item(0).unwrap();
+            item.item_type_id()
+        });
+        assert_eq!(handle.join().unwrap(), Some(apple));
+        assert_static_send(&registry);
+    }
+}
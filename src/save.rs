@@ -0,0 +1,348 @@
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::serialization::{with_context, with_saved_versions, NO_CONTEXT};
+
+/// Version of the layout `SaveGame` writes. Saves with a newer version are rejected when loading.
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// A versioned save blob around any serializable content, like whole inventories.
+///
+/// Next to the content, a save stores the format version and the schema version of every versioned `ItemData`,
+/// see `ItemRegistry::register_data_versioned`. When loading, data saved with an older schema version is
+/// upgraded through the migrations registered with `ItemRegistry::register_migration`.
+/// Like items, saves can only be (de)serialized inside of `ItemRegistry::serde_scope`.
+///
+/// # Examples
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use shrub::{InventorySelector, ItemData, ItemRegistry, ItemType, SaveGame, SlotInventory};
+///
+/// #[derive(ItemData, Serialize, Deserialize)]
+/// struct Durability {
+///     current: u32,
+///     max: u32,
+/// }
+///
+/// let mut registry = ItemRegistry::new();
+/// registry.register_data_versioned::<Durability>("core:durability", 1);
+/// registry.register_migration("core:durability", 0, |current: u32| Durability { current, max: 100 });
+/// let sword = registry.register("core:sword", ItemType::new());
+///
+/// // a save from before `Durability` had a maximum
+/// let old_save = r#"{
+///     "format_version": 1,
+///     "data_versions": {},
+///     "content": { "slots": [{ "type": "core:sword", "count": 1, "data": { "core:durability": 42 } }] }
+/// }"#;
+/// let save: SaveGame<SlotInventory> = registry.serde_scope(|| serde_json::from_str(old_save)).unwrap();
+/// let inventory = save.into_content();
+/// let durability = inventory.get_item(0).unwrap().get_data::<Durability>().unwrap();
+/// assert_eq!((durability.current, durability.max), (42, 100));
+/// ```
+#[derive(Debug)]
+pub struct SaveGame<T> {
+    format_version: u32,
+    content: T,
+}
+
+impl<T> SaveGame<T> {
+    /// Creates a new SaveGame with the current format version.
+    pub fn new(content: T) -> Self {
+        SaveGame {
+            format_version: SAVE_FORMAT_VERSION,
+            content,
+        }
+    }
+
+    /// Returns the format version the save was written with.
+    #[inline]
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    /// Borrows the saved content.
+    #[inline]
+    pub fn content(&self) -> &T {
+        &self.content
+    }
+
+    /// Takes the saved content out of the save.
+    #[inline]
+    pub fn into_content(self) -> T {
+        self.content
+    }
+}
+
+impl<T: Serialize> Serialize for SaveGame<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data_versions: BTreeMap<String, u32> = with_context(|registry| {
+            let registry = registry.ok_or_else(|| ser::Error::custom(NO_CONTEXT))?;
+            let versions = registry.data_tags.versions();
+            Ok(versions
+                .map(|(tag, version)| (tag.to_owned(), version))
+                .collect())
+        })?;
+        let mut save = serializer.serialize_struct("SaveGame", 3)?;
+        save.serialize_field("format_version", &self.format_version)?;
+        save.serialize_field("data_versions", &data_versions)?;
+        save.serialize_field("content", &self.content)?;
+        save.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SaveGame<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("SaveGame", SAVE_FIELDS, SaveGameVisitor(PhantomData))
+    }
+}
+
+const SAVE_FIELDS: &[&str] = &["format_version", "data_versions", "content"];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum SaveField {
+    FormatVersion,
+    DataVersions,
+    Content,
+}
+
+struct SaveGameVisitor<T>(PhantomData<T>);
+
+fn check_format_version<E: de::Error>(format_version: u32) -> Result<u32, E> {
+    if format_version > SAVE_FORMAT_VERSION {
+        return Err(E::custom(format_args!(
+            "save format version {format_version} is newer than the supported version {SAVE_FORMAT_VERSION}"
+        )));
+    }
+    Ok(format_version)
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SaveGameVisitor<T> {
+    type Value = SaveGame<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a save game")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let format_version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let format_version = check_format_version(format_version)?;
+        let data_versions = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let content = with_saved_versions(data_versions, || seq.next_element())?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok(SaveGame {
+            format_version,
+            content,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut format_version, mut data_versions, mut content) = (None, None, None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                SaveField::FormatVersion => {
+                    format_version = Some(check_format_version(map.next_value()?)?)
+                }
+                SaveField::DataVersions => data_versions = Some(map.next_value()?),
+                SaveField::Content => {
+                    let data_versions = data_versions.take().ok_or_else(|| {
+                        de::Error::custom("`data_versions` has to come before `content`")
+                    })?;
+                    content = Some(with_saved_versions(data_versions, || map.next_value())?);
+                }
+            }
+        }
+        Ok(SaveGame {
+            format_version: format_version
+                .ok_or_else(|| de::Error::missing_field("format_version"))?,
+            content: content.ok_or_else(|| de::Error::missing_field("content"))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        Inventory, InventorySelector, ItemData, ItemRegistry, ItemType, MaxStackSize, SaveGame,
+        SlotInventory,
+    };
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Durability {
+        current: u32,
+        max: u32,
+    }
+    impl ItemData for Durability {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Backpack(SlotInventory);
+    impl ItemData for Backpack {}
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Enchantment(String);
+    impl ItemData for Enchantment {}
+
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::new();
+        registry.register_data_versioned::<Durability>("test:durability", 2);
+        registry.register_data::<Backpack>("test:backpack");
+        registry.register("test:sword", ItemType::new());
+        registry.register("test:arrow", ItemType::with_data(MaxStackSize(64)));
+        registry.register("test:backpack", ItemType::new());
+        registry
+    }
+
+    #[test]
+    fn save_nested_inventories() {
+        let registry = registry();
+        let mut backpack = SlotInventory::new(2);
+        let mut arrows = registry[registry.id_of("test:arrow").unwrap()].item_new();
+        arrows.set_count(30);
+        backpack.add_item(arrows);
+        let mut inventory = SlotInventory::new(3);
+        inventory.insert_item(
+            2,
+            registry[registry.id_of("test:backpack").unwrap()].item_with_data(Backpack(backpack)),
+        );
+        inventory.add_item(
+            registry[registry.id_of("test:sword").unwrap()]
+                .item_with_data(Durability { current: 5, max: 9 }),
+        );
+
+        let json = registry
+            .serde_scope(|| serde_json::to_string(&SaveGame::new(&inventory)))
+            .unwrap();
+        assert!(json.starts_with(
+            r#"{"format_version":1,"data_versions":{"test:durability":2},"content":{"slots":["#
+        ));
+
+        let save: SaveGame<SlotInventory> = registry
+            .serde_scope(|| serde_json::from_str(&json))
+            .unwrap();
+        assert_eq!(save.format_version(), 1);
+        let inventory = save.into_content();
+        assert_eq!(
+            inventory
+                .get_item(0)
+                .unwrap()
+                .get_data::<Durability>()
+                .as_deref(),
+            Some(&Durability { current: 5, max: 9 })
+        );
+        assert!(inventory.get_item(1).is_none());
+        let backpack = &inventory
+            .get_item(2)
+            .unwrap()
+            .get_data::<Backpack>()
+            .unwrap()
+            .0;
+        assert_eq!(backpack.get_item(0).unwrap().count(), 30);
+    }
+
+    #[test]
+    fn migrate_old_data() {
+        let mut registry = registry();
+        registry.register_migration("test:durability", 0, |current: u32| (current, current));
+        registry.register_migration("test:durability", 1, |(current, max): (u32, u32)| {
+            Durability { current, max }
+        });
+
+        let old_save = r#"{"format_version":1,"data_versions":{},"content":{"slots":[{"type":"test:sword","count":1,"data":{"test:durability":12}}]}}"#;
+        let save: SaveGame<SlotInventory> = registry
+            .serde_scope(|| serde_json::from_str(old_save))
+            .unwrap();
+        assert_eq!(
+            save.content()
+                .get_item(0)
+                .unwrap()
+                .get_data::<Durability>()
+                .as_deref(),
+            Some(&Durability {
+                current: 12,
+                max: 12
+            })
+        );
+
+        let save = r#"{"format_version":1,"data_versions":{"test:durability":1},"content":{"slots":[{"type":"test:sword","count":1,"data":{"test:durability":[3,4]}}]}}"#;
+        let save: SaveGame<SlotInventory> =
+            registry.serde_scope(|| serde_json::from_str(save)).unwrap();
+        assert_eq!(
+            save.content()
+                .get_item(0)
+                .unwrap()
+                .get_data::<Durability>()
+                .as_deref(),
+            Some(&Durability { current: 3, max: 4 })
+        );
+    }
+
+    #[test]
+    fn migrate_with_bincode() {
+        let mut registry = registry();
+        registry.register_data_versioned::<Enchantment>("test:enchantment", 1);
+        registry.register_migration("test:enchantment", 0, |level: u8| {
+            Enchantment(format!("sharpness {level}"))
+        });
+        let sword = registry.id_of("test:sword").unwrap();
+
+        // a save written while enchantments were stored as their level
+        let mut old_registry = ItemRegistry::new();
+        #[derive(Serialize, Deserialize)]
+        struct OldEnchantment(u8);
+        impl ItemData for OldEnchantment {}
+        old_registry.register_data::<OldEnchantment>("test:enchantment");
+        let old_sword = old_registry.register("test:sword", ItemType::new());
+        let mut inventory = SlotInventory::new(1);
+        inventory.add_item(old_registry[old_sword].item_with_data(OldEnchantment(3)));
+        let bytes = old_registry
+            .serde_scope(|| bincode::serialize(&SaveGame::new(&inventory)))
+            .unwrap();
+
+        let save: SaveGame<SlotInventory> = registry
+            .serde_scope(|| bincode::deserialize(&bytes))
+            .unwrap();
+        let item = save.content().get_item(0).unwrap();
+        assert_eq!(item.item_type_id(), Some(sword));
+        assert_eq!(
+            item.get_data::<Enchantment>().as_deref(),
+            Some(&Enchantment("sharpness 3".into()))
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_versions() {
+        let mut registry = registry();
+        registry.serde_scope(|| {
+            let newer_format = r#"{"format_version":2,"data_versions":{},"content":{"slots":[]}}"#;
+            assert!(serde_json::from_str::<SaveGame<SlotInventory>>(newer_format).is_err());
+
+            let newer_data = r#"{"format_version":1,"data_versions":{"test:durability":3},"content":{"slots":[{"type":"test:sword","count":1,"data":{"test:durability":{"current":1,"max":1}}}]}}"#;
+            assert!(serde_json::from_str::<SaveGame<SlotInventory>>(newer_data).is_err());
+
+            let missing_migration = r#"{"format_version":1,"data_versions":{},"content":{"slots":[{"type":"test:sword","count":1,"data":{"test:durability":1}}]}}"#;
+            assert!(serde_json::from_str::<SaveGame<SlotInventory>>(missing_migration).is_err());
+        });
+
+        registry.register_migration("test:durability", 0, |current: u32| current as u64);
+        registry.register_migration("test:durability", 1, |current: u32| Durability {
+            current,
+            max: current,
+        });
+        let broken_chain = r#"{"format_version":1,"data_versions":{},"content":{"slots":[{"type":"test:sword","count":1,"data":{"test:durability":1}}]}}"#;
+        assert!(registry
+            .serde_scope(|| serde_json::from_str::<SaveGame<SlotInventory>>(broken_chain))
+            .is_err());
+    }
+}
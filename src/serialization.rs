@@ -0,0 +1,766 @@
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+};
+
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    item::ItemDataMap,
+    load::{placeholder_type, report_unknown_data, unknown_policy},
+    Footprint, Item, ItemData, ItemRegistry, ItemType, MaxStackSize, PreservedData, UnknownPolicy,
+    Volume, Weight,
+};
+
+type SerializeFn = fn(&dyn ItemData) -> &dyn erased_serde::Serialize;
+type DeserializeFn =
+    fn(&mut dyn erased_serde::Deserializer) -> Result<Box<dyn ItemData>, erased_serde::Error>;
+type DeserializeAnyFn =
+    fn(&mut dyn erased_serde::Deserializer) -> Result<Box<dyn Any>, erased_serde::Error>;
+type FromAnyFn = fn(Box<dyn Any>) -> Option<Box<dyn ItemData>>;
+type UpgradeFn = Box<dyn Fn(Box<dyn Any>) -> Option<Box<dyn Any>> + Send + Sync>;
+
+/// How a registered `ItemData` type is written and read.
+pub(crate) struct DataRegistration {
+    tag: Box<str>,
+    version: u32,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+    from_any: FromAnyFn,
+    migrations: HashMap<u32, Migration>,
+}
+
+/// Upgrades data saved with an old schema version to the next version.
+struct Migration {
+    deserialize: DeserializeAnyFn,
+    upgrade: UpgradeFn,
+}
+
+/// Maps `ItemData` types to their stable string tags, which are used in place of the type when serializing.
+#[derive(Default)]
+pub(crate) struct ItemDataTags {
+    by_type: HashMap<TypeId, DataRegistration>,
+    by_tag: HashMap<Box<str>, TypeId>,
+}
+
+impl ItemDataTags {
+    /// Creates the tags for the `ItemData` types that come with shrub.
+    pub(crate) fn new() -> Self {
+        let mut tags = ItemDataTags::default();
+        tags.register::<MaxStackSize>("shrub:max_stack_size", 0);
+        tags.register::<Footprint>("shrub:footprint", 0);
+        tags.register::<Weight>("shrub:weight", 0);
+        tags.register::<Volume>("shrub:volume", 0);
+        tags
+    }
+
+    fn register<D: ItemData + Serialize + de::DeserializeOwned>(
+        &mut self,
+        tag: &str,
+        version: u32,
+    ) {
+        assert!(
+            !self.by_tag.contains_key(tag),
+            "item data tag `{tag}` is already registered"
+        );
+        let registration = DataRegistration {
+            tag: tag.into(),
+            version,
+            serialize: serialize_data::<D>,
+            deserialize: deserialize_data::<D>,
+            from_any: data_from_any::<D>,
+            migrations: HashMap::new(),
+        };
+        if let Some(old) = self.by_type.insert(TypeId::of::<D>(), registration) {
+            self.by_tag.remove(&old.tag);
+        }
+        self.by_tag.insert(tag.into(), TypeId::of::<D>());
+    }
+
+    pub(crate) fn get_by_tag(&self, tag: &str) -> Option<(TypeId, &DataRegistration)> {
+        let type_id = *self.by_tag.get(tag)?;
+        Some((type_id, self.by_type.get(&type_id)?))
+    }
+
+    /// Returns the tag the data type is registered under.
+    pub(crate) fn tag_of(&self, type_id: &TypeId) -> Option<&str> {
+        Some(&self.by_type.get(type_id)?.tag)
+    }
+
+    /// Serializes registered data into a self-describing value, which can be compared with other values.
+    /// Returns `None` when the data type isn't registered or serializing fails.
+    pub(crate) fn to_value(
+        &self,
+        type_id: &TypeId,
+        data: &dyn ItemData,
+    ) -> Option<serde_value::Value> {
+        let registration = self.by_type.get(type_id)?;
+        serde_value::to_value((registration.serialize)(data)).ok()
+    }
+
+    /// Iterates over the tags and schema versions of all data types which aren't at version 0.
+    pub(crate) fn versions(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        self.by_type
+            .values()
+            .filter(|registration| registration.version != 0)
+            .map(|registration| (&*registration.tag, registration.version))
+    }
+}
+
+impl fmt::Debug for ItemDataTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.by_tag.keys()).finish()
+    }
+}
+
+fn serialize_data<D: ItemData + Serialize>(data: &dyn ItemData) -> &dyn erased_serde::Serialize {
+    data.downcast_ref::<D>().unwrap()
+}
+
+fn deserialize_data<D: ItemData + de::DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer,
+) -> Result<Box<dyn ItemData>, erased_serde::Error> {
+    Ok(Box::new(erased_serde::deserialize::<D>(deserializer)?))
+}
+
+fn deserialize_any<T: de::DeserializeOwned + 'static>(
+    deserializer: &mut dyn erased_serde::Deserializer,
+) -> Result<Box<dyn Any>, erased_serde::Error> {
+    Ok(Box::new(erased_serde::deserialize::<T>(deserializer)?))
+}
+
+fn data_from_any<D: ItemData>(data: Box<dyn Any>) -> Option<Box<dyn ItemData>> {
+    Some(data.downcast::<D>().ok()?)
+}
+
+/// `ItemData` that knows the tag it is serialized under, so it can be registered with `ItemRegistry::register_serde_data`.
+/// Usually implemented with `#[item_data(serde = "tag")]` on the derive.
+///
+/// # Examples
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use shrub::{ItemData, ItemRegistry, SerdeData};
+///
+/// #[derive(ItemData, Serialize, Deserialize)]
+/// #[item_data(serde = "core:durability")]
+/// struct Durability(u32);
+///
+/// let mut registry = ItemRegistry::new();
+/// registry.register_serde_data::<Durability>();
+/// assert_eq!(registry.data_tag::<Durability>(), Some(Durability::TAG));
+/// ```
+pub trait SerdeData: ItemData + Serialize + de::DeserializeOwned {
+    /// The stable tag the data is serialized under.
+    const TAG: &'static str;
+    /// The current schema version of the data, see `ItemRegistry::register_data_versioned`.
+    const VERSION: u32 = 0;
+}
+
+impl ItemRegistry {
+    /// Registers a stable tag for an `ItemData` type, so items and item types carrying it can be (de)serialized.
+    /// Registering the same type again replaces its previous tag.
+    ///
+    /// # Panics
+    /// Panics when another type is already registered under the tag.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use shrub::{ItemData, ItemRegistry, ItemType};
+    ///
+    /// #[derive(ItemData, Serialize, Deserialize)]
+    /// struct Durability(u32);
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// registry.register_data::<Durability>("core:durability");
+    /// let sword = registry.register("core:sword", ItemType::new());
+    ///
+    /// let item = registry[sword].item_with_data(Durability(80));
+    /// let json = registry.serde_scope(|| serde_json::to_string(&item)).unwrap();
+    /// assert_eq!(json, r#"{"type":"core:sword","count":1,"data":{"core:durability":80}}"#);
+    /// ```
+    pub fn register_data<D: ItemData + Serialize + de::DeserializeOwned>(&mut self, tag: &str) {
+        self.data_tags.register::<D>(tag, 0);
+    }
+
+    /// Like `register_data_versioned`, but with the tag and version the data type declares itself.
+    pub fn register_serde_data<D: SerdeData>(&mut self) {
+        self.data_tags.register::<D>(D::TAG, D::VERSION);
+    }
+
+    /// Like `register_data`, but with the current schema version of the data type.
+    /// Saves remember the version of their data, so data from older versions can be upgraded with `register_migration` when loading a `SaveGame`.
+    /// `register_data` registers at version 0.
+    pub fn register_data_versioned<D: ItemData + Serialize + de::DeserializeOwned>(
+        &mut self,
+        tag: &str,
+        version: u32,
+    ) {
+        self.data_tags.register::<D>(tag, version);
+    }
+
+    /// Registers how to upgrade data of the given tag from schema version `from_version` to `from_version + 1`.
+    ///
+    /// `Old` is how the data looked at `from_version`, `New` how it looks one version later.
+    /// When loading, migrations are chained until the current version is reached, so the `New` of one migration
+    /// has to be the `Old` of the next one and the last migration has to produce the registered data type.
+    ///
+    /// # Panics
+    /// Panics when no data is registered under the tag, or `from_version` isn't older than its current version.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use shrub::{ItemData, ItemRegistry};
+    ///
+    /// // version 0 only stored the current durability as a plain number
+    /// #[derive(ItemData, Serialize, Deserialize)]
+    /// struct Durability {
+    ///     current: u32,
+    ///     max: u32,
+    /// }
+    ///
+    /// let mut registry = ItemRegistry::new();
+    /// registry.register_data_versioned::<Durability>("core:durability", 1);
+    /// registry.register_migration("core:durability", 0, |current: u32| Durability { current, max: 100 });
+    /// ```
+    pub fn register_migration<Old, New>(
+        &mut self,
+        tag: &str,
+        from_version: u32,
+        migrate: impl Fn(Old) -> New + Send + Sync + 'static,
+    ) where
+        Old: de::DeserializeOwned + 'static,
+        New: 'static,
+    {
+        let type_id = *self
+            .data_tags
+            .by_tag
+            .get(tag)
+            .unwrap_or_else(|| panic!("item data tag `{tag}` isn't registered"));
+        let registration = self.data_tags.by_type.get_mut(&type_id).unwrap();
+        assert!(
+            from_version < registration.version,
+            "item data `{tag}` is at version {}, it can't be migrated from version {from_version}",
+            registration.version
+        );
+        let upgrade: UpgradeFn = Box::new(move |old: Box<dyn Any>| {
+            let new: Box<dyn Any> = Box::new(migrate(*old.downcast::<Old>().ok()?));
+            Some(new)
+        });
+        let migration = Migration {
+            deserialize: deserialize_any::<Old>,
+            upgrade,
+        };
+        registration.migrations.insert(from_version, migration);
+    }
+
+    /// Returns the current schema version of the `ItemData` type.
+    pub fn data_version<D: ItemData>(&self) -> Option<u32> {
+        Some(self.data_tags.by_type.get(&TypeId::of::<D>())?.version)
+    }
+
+    /// Returns the tag the `ItemData` type was registered under.
+    pub fn data_tag<D: ItemData>(&self) -> Option<&str> {
+        Some(&self.data_tags.by_type.get(&TypeId::of::<D>())?.tag)
+    }
+
+    /// Makes this registry the lookup context for (de)serializing `Item`s and `ItemType`s inside `f`.
+    ///
+    /// Items reference their type by key and data by tag, so (de)serializing them outside of a scope fails with an error.
+    pub fn serde_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore(*const ItemRegistry);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CONTEXT.with(|context| context.set(self.0));
+            }
+        }
+        let _restore = Restore(CONTEXT.with(|context| context.replace(self)));
+        f()
+    }
+}
+
+thread_local! {
+    static CONTEXT: Cell<*const ItemRegistry> = const { Cell::new(std::ptr::null()) };
+    static SAVED_VERSIONS: RefCell<Option<HashMap<String, u32>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` while data is read as if it was saved with the given schema versions.
+/// Tags missing from `versions` are treated as version 0.
+pub(crate) fn with_saved_versions<R>(versions: HashMap<String, u32>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<HashMap<String, u32>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SAVED_VERSIONS.with(|saved| *saved.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(SAVED_VERSIONS.with(|saved| saved.replace(Some(versions))));
+    f()
+}
+
+/// Splits the version off a data tag like `mod:enchantment@2`, which is how preserved data is written
+/// when it was loaded with a schema version, see `PreservedData`.
+fn split_versioned_tag(tag: &str) -> Option<(&str, u32)> {
+    let (tag, version) = tag.rsplit_once('@')?;
+    Some((tag, version.parse().ok()?))
+}
+
+/// Returns the schema version the data of the given tag was saved with,
+/// or `None` when the data isn't read from a `SaveGame` and thus is at its current version.
+fn saved_version(tag: &str) -> Option<u32> {
+    SAVED_VERSIONS.with(|saved| {
+        let saved = saved.borrow();
+        Some(saved.as_ref()?.get(tag).copied().unwrap_or(0))
+    })
+}
+
+/// Runs `f` with the registry of the innermost `serde_scope`.
+pub(crate) fn with_context<R>(f: impl FnOnce(Option<&ItemRegistry>) -> R) -> R {
+    // SAFETY: the pointer is only set by `serde_scope`, which borrows the registry until it resets the pointer
+    let registry = unsafe { CONTEXT.with(Cell::get).as_ref() };
+    f(registry)
+}
+
+pub(crate) const NO_CONTEXT: &str =
+    "items can only be (de)serialized inside of `ItemRegistry::serde_scope`";
+
+/// Serializes an `ItemDataMap` as a map from data tags to data, sorted by tag.
+struct DataMapSer<'a>(&'a ItemDataMap);
+
+impl Serialize for DataMapSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        with_context(|registry| {
+            let registry = registry.ok_or_else(|| ser::Error::custom(NO_CONTEXT))?;
+            let mut entries = Vec::with_capacity(self.0.len());
+            for (type_id, data) in self.0 {
+                if let Some(preserved) = data.downcast_ref::<PreservedData>() {
+                    entries.extend(preserved.entries.iter().map(|(tag, value)| {
+                        let tag = match preserved.versions.get(tag) {
+                            Some(version) => Cow::Owned(format!("{tag}@{version}")),
+                            None => Cow::Borrowed(tag.as_str()),
+                        };
+                        (tag, value as &dyn erased_serde::Serialize)
+                    }));
+                    continue;
+                }
+                let registration = registry.data_tags.by_type.get(type_id).ok_or_else(|| {
+                    ser::Error::custom("item data without a registered tag can't be serialized")
+                })?;
+                entries.push((
+                    Cow::Borrowed(&*registration.tag),
+                    (registration.serialize)(data.as_ref()),
+                ));
+            }
+            entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+            let mut map = serializer.serialize_map(Some(entries.len()))?;
+            for (tag, data) in entries {
+                map.serialize_entry(&tag, data)?;
+            }
+            map.end()
+        })
+    }
+}
+
+/// Deserializes a map from data tags to data into an `ItemDataMap`.
+struct DataMapSeed<'r>(&'r ItemRegistry);
+
+impl<'de> DeserializeSeed<'de> for DataMapSeed<'_> {
+    type Value = ItemDataMap;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DataMapSeed<'_> {
+    type Value = ItemDataMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of item data tags to item data")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut data = ItemDataMap::with_capacity(map.size_hint().unwrap_or(0));
+        let mut preserved = PreservedData::default();
+        while let Some(key) = map.next_key::<String>()? {
+            let (tag, saved) = match split_versioned_tag(&key) {
+                Some((tag, version)) => (tag.to_owned(), Some(version)),
+                None => (key.clone(), saved_version(&key)),
+            };
+            let Some((type_id, registration)) = self.0.data_tags.get_by_tag(&tag) else {
+                match unknown_policy() {
+                    UnknownPolicy::Error => {
+                        return Err(de::Error::custom(format_args!(
+                            "unknown item data tag `{tag}`"
+                        )))
+                    }
+                    UnknownPolicy::Drop => {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                    UnknownPolicy::Preserve => {
+                        let value = map.next_value()?;
+                        if let Some(version @ 1..) = saved {
+                            preserved.versions.insert(tag.clone(), version);
+                        }
+                        preserved.entries.insert(tag.clone(), value);
+                    }
+                }
+                report_unknown_data(&tag);
+                continue;
+            };
+            let value = match saved {
+                Some(version) if version != registration.version => {
+                    map.next_value_seed(MigrationSeed(registration, version))?
+                }
+                _ => map.next_value_seed(DataSeed(registration))?,
+            };
+            data.insert(type_id, value);
+        }
+        if !preserved.entries.is_empty() {
+            data.insert(TypeId::of::<PreservedData>(), Box::new(preserved));
+        }
+        Ok(data)
+    }
+}
+
+/// Deserializes a single `ItemData` with its registered deserializer.
+pub(crate) struct DataSeed<'r>(pub(crate) &'r DataRegistration);
+
+impl<'de> DeserializeSeed<'de> for DataSeed<'_> {
+    type Value = Box<dyn ItemData>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.0.deserialize)(&mut erased).map_err(de::Error::custom)
+    }
+}
+
+/// Deserializes data saved with an old schema version and upgrades it to the current one.
+struct MigrationSeed<'r>(&'r DataRegistration, u32);
+
+impl<'de> DeserializeSeed<'de> for MigrationSeed<'_> {
+    type Value = Box<dyn ItemData>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let MigrationSeed(registration, saved_version) = self;
+        let tag = &registration.tag;
+        if saved_version > registration.version {
+            return Err(de::Error::custom(format_args!(
+                "item data `{tag}` was saved with version {saved_version}, which is newer than version {}",
+                registration.version
+            )));
+        }
+        let missing_migration = |version| {
+            de::Error::custom(format_args!(
+                "item data `{tag}` has no migration from version {version}"
+            ))
+        };
+
+        let first = registration
+            .migrations
+            .get(&saved_version)
+            .ok_or_else(|| missing_migration(saved_version))?;
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        let mut data = (first.deserialize)(&mut erased).map_err(de::Error::custom)?;
+        for version in saved_version..registration.version {
+            let migration = registration
+                .migrations
+                .get(&version)
+                .ok_or_else(|| missing_migration(version))?;
+            data = (migration.upgrade)(data).ok_or_else(|| {
+                de::Error::custom(format_args!(
+                    "migration of item data `{tag}` from version {version} doesn't accept the data of the previous migration"
+                ))
+            })?;
+        }
+        (registration.from_any)(data).ok_or_else(|| {
+            de::Error::custom(format_args!(
+                "migrations of item data `{tag}` don't produce the registered data type"
+            ))
+        })
+    }
+}
+
+impl Serialize for Item {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let key = self
+            .item_type()
+            .key()
+            .ok_or_else(|| ser::Error::custom("items of unregistered types can't be serialized"))?;
+        let mut item = serializer.serialize_struct("Item", 3)?;
+        item.serialize_field("type", key)?;
+        item.serialize_field("count", &self.count)?;
+        item.serialize_field("data", &DataMapSer(&self.data))?;
+        item.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        with_context(|registry| {
+            let registry = registry.ok_or_else(|| de::Error::custom(NO_CONTEXT))?;
+            deserializer.deserialize_struct("Item", ITEM_FIELDS, ItemVisitor(registry))
+        })
+    }
+}
+
+const ITEM_FIELDS: &[&str] = &["type", "count", "data"];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ItemField {
+    Type,
+    Count,
+    Data,
+}
+
+struct ItemVisitor<'r>(&'r ItemRegistry);
+
+impl ItemVisitor<'_> {
+    fn item_type<E: de::Error>(&self, key: &str) -> Result<Arc<ItemType>, E> {
+        self.0
+            .get_by_key(key)
+            .cloned()
+            .or_else(|| placeholder_type(key))
+            .ok_or_else(|| E::custom(format_args!("unknown item type `{key}`")))
+    }
+}
+
+impl<'de> Visitor<'de> for ItemVisitor<'_> {
+    type Value = Item;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an item")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let key: String = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let count = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let data = seq
+            .next_element_seed(DataMapSeed(self.0))?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let mut item = Item::new(self.item_type(&key)?);
+        item.count = count;
+        item.data = data;
+        Ok(item)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut item_type, mut count, mut data) = (None, None, None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                ItemField::Type => item_type = Some(self.item_type(&map.next_value::<String>()?)?),
+                ItemField::Count => count = Some(map.next_value()?),
+                ItemField::Data => data = Some(map.next_value_seed(DataMapSeed(self.0))?),
+            }
+        }
+        let mut item = Item::new(item_type.ok_or_else(|| de::Error::missing_field("type"))?);
+        item.count = count.unwrap_or(1);
+        item.data = data.unwrap_or_default();
+        Ok(item)
+    }
+}
+
+/// Deserializes the slots of a `SlotInventory`, emptying the slots of items with missing types when they are dropped.
+pub(crate) fn deserialize_slots<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Option<Item>>, D::Error> {
+    let mut slots = Vec::<Option<Item>>::deserialize(deserializer)?;
+    if unknown_policy() == UnknownPolicy::Drop {
+        for slot in &mut slots {
+            if slot
+                .as_ref()
+                .is_some_and(|item| item.item_type().is_placeholder())
+            {
+                *slot = None;
+            }
+        }
+    }
+    Ok(slots)
+}
+
+impl Serialize for ItemType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut item_type = serializer.serialize_struct("ItemType", 1)?;
+        item_type.serialize_field("data", &DataMapSer(&self.definition().data))?;
+        item_type.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    /// Deserializes an unregistered ItemType, which can be registered in the `ItemRegistry` afterwards.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        with_context(|registry| {
+            let registry = registry.ok_or_else(|| de::Error::custom(NO_CONTEXT))?;
+            deserializer.deserialize_struct("ItemType", &["data"], ItemTypeVisitor(registry))
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ItemTypeField {
+    Data,
+}
+
+struct ItemTypeVisitor<'r>(&'r ItemRegistry);
+
+impl<'de> Visitor<'de> for ItemTypeVisitor<'_> {
+    type Value = ItemType;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an item type")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let data = seq
+            .next_element_seed(DataMapSeed(self.0))?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let mut item_type = ItemType::new();
+        *item_type.data_mut() = data;
+        Ok(item_type)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut item_type = ItemType::new();
+        while let Some(ItemTypeField::Data) = map.next_key()? {
+            *item_type.data_mut() = map.next_value_seed(DataMapSeed(self.0))?;
+        }
+        Ok(item_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{Item, ItemData, ItemRegistry, ItemType, MaxStackSize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Durability {
+        current: u32,
+        max: u32,
+    }
+    impl ItemData for Durability {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Name(String);
+    impl ItemData for Name {}
+
+    struct Secret;
+    impl ItemData for Secret {}
+
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::new();
+        registry.register_data::<Durability>("test:durability");
+        registry.register_data::<Name>("test:name");
+        registry.register("test:sword", ItemType::with_data(MaxStackSize(4)));
+        registry
+    }
+
+    #[test]
+    fn item_json_roundtrip() {
+        let registry = registry();
+        let sword = registry.id_of("test:sword").unwrap();
+        let mut item = registry[sword].item_with_data((
+            Durability {
+                current: 3,
+                max: 10,
+            },
+            Name("Excalibur".into()),
+        ));
+        item.set_count(2);
+
+        let json = registry
+            .serde_scope(|| serde_json::to_string(&item))
+            .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"test:sword","count":2,"data":{"test:durability":{"current":3,"max":10},"test:name":"Excalibur"}}"#
+        );
+
+        let item: Item = registry
+            .serde_scope(|| serde_json::from_str(&json))
+            .unwrap();
+        assert_eq!(item.item_type_id(), Some(sword));
+        assert_eq!(item.count(), 2);
+        assert_eq!(
+            item.get_data::<Durability>().as_deref(),
+            Some(&Durability {
+                current: 3,
+                max: 10
+            })
+        );
+        assert_eq!(item.get_data::<Name>().unwrap().0, "Excalibur");
+        assert_eq!(item.max_stack_size(), 4);
+    }
+
+    #[test]
+    fn item_bincode_roundtrip() {
+        let registry = registry();
+        let sword = registry.id_of("test:sword").unwrap();
+        let item = registry[sword].item_with_data(Durability { current: 1, max: 2 });
+
+        let bytes = registry.serde_scope(|| bincode::serialize(&item)).unwrap();
+        let item: Item = registry
+            .serde_scope(|| bincode::deserialize(&bytes))
+            .unwrap();
+        assert_eq!(item.item_type_id(), Some(sword));
+        assert_eq!(
+            item.get_data::<Durability>().as_deref(),
+            Some(&Durability { current: 1, max: 2 })
+        );
+    }
+
+    #[test]
+    fn item_type_roundtrip() {
+        let registry = registry();
+        let sword = &registry[registry.id_of("test:sword").unwrap()];
+        let json = registry
+            .serde_scope(|| serde_json::to_string(sword.as_ref()))
+            .unwrap();
+        assert_eq!(json, r#"{"data":{"shrub:max_stack_size":4}}"#);
+
+        let item_type: ItemType = registry
+            .serde_scope(|| serde_json::from_str(&json))
+            .unwrap();
+        assert_eq!(item_type.max_stack_size(), 4);
+        assert!(item_type.id().is_none());
+    }
+
+    #[test]
+    fn serialize_requires_scope() {
+        let registry = registry();
+        let item = registry[registry.id_of("test:sword").unwrap()].item_new();
+        assert!(serde_json::to_string(&item).is_err());
+        assert!(serde_json::from_str::<Item>(r#"{"type":"test:sword"}"#).is_err());
+    }
+
+    #[test]
+    fn reject_unknown_types_and_data() {
+        let registry = registry();
+        registry.serde_scope(|| {
+            assert!(serde_json::from_str::<Item>(r#"{"type":"test:shield"}"#).is_err());
+            assert!(serde_json::from_str::<Item>(
+                r#"{"type":"test:sword","data":{"test:mana":3}}"#
+            )
+            .is_err());
+
+            let item = registry[registry.id_of("test:sword").unwrap()].item_with_data(Secret);
+            assert!(serde_json::to_string(&item).is_err());
+        });
+    }
+}
@@ -0,0 +1,686 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    AddReceipt, Inventory, InventoryEntries, InventoryItems, InventorySelector, Item, ItemFilter,
+    ShrubError, TransactionInventory,
+};
+
+/// A fixed-capacity Inventory where every item occupies one slot, selected by its `usize` index.
+///
+/// The whole inventory and single slots can be restricted to certain items with an `ItemFilter`.
+/// Filters aren't serialized, so they have to be set up again after loading.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{Inventory, InventorySelector, ItemData, ItemFilter, ItemType, SlotInventory};
+///
+/// struct Helmet;
+/// impl ItemData for Helmet {}
+///
+/// let helmet = Arc::new(ItemType::with_data(Helmet));
+/// let stone = Arc::new(ItemType::new());
+/// let mut equipment = SlotInventory::new(3);
+/// equipment.set_slot_filter(0, Some(ItemFilter::has_data::<Helmet>()));
+///
+/// assert!(!equipment.slot_accepts(0, &stone.item_new())); // grey out the head slot while dragging a stone
+/// assert!(equipment.insert_item(0, stone.item_new()).is_some());
+/// assert!(equipment.add_item(stone.item_new()).is_none());
+/// assert!(equipment.get_item(1).is_some());
+/// assert!(equipment.insert_item(0, helmet.item_new()).is_none());
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlotInventory {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::deserialize_slots")
+    )]
+    slots: Vec<Option<Item>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filter: Option<ItemFilter>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    slot_filters: BTreeMap<usize, ItemFilter>,
+}
+
+impl SlotInventory {
+    /// Creates a new SlotInventory with the given amount of empty slots.
+    pub fn new(capacity: usize) -> Self {
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || None);
+        SlotInventory {
+            slots,
+            filter: None,
+            slot_filters: BTreeMap::new(),
+        }
+    }
+
+    /// Restricts the whole inventory to the items the filter accepts.
+    pub fn with_filter(mut self, filter: ItemFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Returns the filter of the whole inventory.
+    #[inline]
+    pub fn filter(&self) -> Option<&ItemFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Changes the filter of the whole inventory. Items it doesn't accept anymore stay in the inventory.
+    #[inline]
+    pub fn set_filter(&mut self, filter: Option<ItemFilter>) {
+        self.filter = filter;
+    }
+
+    /// Returns the filter of the slot.
+    pub fn slot_filter(&self, slot: usize) -> Option<&ItemFilter> {
+        self.slot_filters.get(&slot)
+    }
+
+    /// Changes the filter of the slot, in addition to the filter of the whole inventory.
+    /// An item the slot doesn't accept anymore stays in it.
+    pub fn set_slot_filter(&mut self, slot: usize, filter: Option<ItemFilter>) {
+        match filter {
+            Some(filter) => self.slot_filters.insert(slot, filter),
+            None => self.slot_filters.remove(&slot),
+        };
+    }
+
+    /// Returns `true` when the filter of the whole inventory accepts the item.
+    pub fn accepts(&self, item: &Item) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(item))
+    }
+
+    /// Returns `true` when the slot exists and both its filter and the filter of the whole inventory accept the item.
+    /// Whether the slot is occupied doesn't matter.
+    pub fn slot_accepts(&self, slot: usize, item: &Item) -> bool {
+        slot < self.slots.len()
+            && self.accepts(item)
+            && self
+                .slot_filters
+                .get(&slot)
+                .is_none_or(|filter| filter.matches(item))
+    }
+
+    /// Iterates over the indices of all slots that accept the item, occupied or not.
+    pub fn accepting_slots<'a>(&'a self, item: &'a Item) -> impl Iterator<Item = usize> + 'a {
+        (0..self.slots.len()).filter(move |&slot| self.slot_accepts(slot, item))
+    }
+
+    /// Returns the amount of slots of the inventory.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the amount of occupied slots.
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Returns `true` when no slot is occupied.
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_none())
+    }
+
+    /// Returns `true` when every slot is occupied.
+    pub fn is_full(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_some())
+    }
+
+    /// Returns `true` when the given slot exists and is empty.
+    #[inline]
+    pub fn is_slot_empty(&self, slot: usize) -> bool {
+        matches!(self.slots.get(slot), Some(None))
+    }
+
+    /// Returns the index of the first empty slot.
+    pub fn first_empty_slot(&self) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.is_none())
+    }
+
+    /// Iterates over the indices of all empty slots.
+    pub fn empty_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.is_none().then_some(index))
+    }
+
+    /// Puts an item into the given slot.
+    /// * when the slot doesn't exist, is already occupied or doesn't accept the item, it will return the item in `Some(Item)`
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{ItemType, SlotInventory};
+    ///
+    /// let item_type = Arc::new(ItemType::new());
+    /// let mut inventory = SlotInventory::new(4);
+    /// assert!(inventory.insert_item(2, item_type.item_new()).is_none());
+    /// assert!(inventory.insert_item(2, item_type.item_new()).is_some()); // slot 2 is occupied now
+    /// assert!(inventory.insert_item(4, item_type.item_new()).is_some()); // there is no slot 4
+    /// ```
+    pub fn insert_item(&mut self, slot: usize, item: Item) -> Option<Item> {
+        self.try_insert_item(slot, item)
+            .err()
+            .and_then(ShrubError::into_item)
+    }
+
+    /// Like `insert_item`, but fails with `ShrubError::SlotOccupied`, `ShrubError::InvalidSlot` or `ShrubError::NotAllowed`.
+    pub fn try_insert_item(&mut self, slot: usize, item: Item) -> Result<(), ShrubError> {
+        if slot < self.slots.len() && !self.slot_accepts(slot, &item) {
+            return Err(ShrubError::NotAllowed(item));
+        }
+        match self.slots.get_mut(slot) {
+            Some(target @ None) => {
+                *target = Some(item);
+                Ok(())
+            }
+            Some(Some(_)) => Err(ShrubError::SlotOccupied(item)),
+            None => Err(ShrubError::InvalidSlot(item)),
+        }
+    }
+
+    /// Swaps the contents of two slots, empty slots included.
+    /// Returns `false` and leaves the inventory untouched when one of the slots doesn't exist
+    /// or doesn't accept the item of the other one.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{InventorySelector, ItemType, SlotInventory};
+    ///
+    /// let item_type = Arc::new(ItemType::new());
+    /// let mut inventory = SlotInventory::new(2);
+    /// inventory.insert_item(0, item_type.item_new());
+    /// assert!(inventory.swap_slots(0, 1));
+    /// assert!(inventory.get_item(0).is_none());
+    /// assert!(inventory.get_item(1).is_some());
+    /// ```
+    pub fn swap_slots(&mut self, a: usize, b: usize) -> bool {
+        if a >= self.slots.len() || b >= self.slots.len() {
+            return false;
+        }
+        let accepted = |slot, item: &Option<Item>| {
+            item.as_ref()
+                .is_none_or(|item| self.slot_accepts(slot, item))
+        };
+        if !accepted(b, &self.slots[a]) || !accepted(a, &self.slots[b]) {
+            return false;
+        }
+        self.slots.swap(a, b);
+        true
+    }
+
+    /// Moves the item of slot `from` into the empty slot `to`.
+    /// Returns `false` and leaves the inventory untouched when `from` is empty, `to` is occupied or doesn't accept the item.
+    pub fn move_item(&mut self, from: usize, to: usize) -> bool {
+        if from == to || !self.is_slot_empty(to) {
+            return false;
+        }
+        match self.get_item(from) {
+            Some(item) if self.slot_accepts(to, item) => {}
+            _ => return false,
+        }
+        self.slots.swap(from, to);
+        true
+    }
+
+    /// Iterates over all occupied slots together with their index.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use shrub::{Inventory, ItemType, SlotInventory};
+    ///
+    /// let item_type = Arc::new(ItemType::new());
+    /// let mut inventory = SlotInventory::new(8);
+    /// inventory.add_item(item_type.item_new());
+    /// inventory.insert_item(5, item_type.item_new());
+    /// let slots: Vec<usize> = inventory.iter().map(|(slot, _item)| slot).collect();
+    /// assert_eq!(slots, vec![0, 5]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Item)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_ref().map(|item| (index, item)))
+    }
+
+    /// Iterates mutably over all occupied slots together with their index.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut Item)> + '_ {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_mut().map(|item| (index, item)))
+    }
+}
+
+impl Inventory for SlotInventory {
+    /// Merges the item into existing compatible stacks first and puts the remainder into the first empty slot.
+    /// Only slots that accept the item are considered. When they are all occupied, only the overflowing remainder is returned.
+    fn add_item(&mut self, mut item: Item) -> Option<Item> {
+        // decided once for the whole item, as filters may depend on the count of the shrinking remainder
+        let accepting: Vec<usize> = self.accepting_slots(&item).collect();
+        for &slot in &accepting {
+            if let Some(stack) = self.slots[slot].as_mut() {
+                item = stack.merge(item)?;
+            }
+        }
+        match accepting.into_iter().find(|&slot| self.is_slot_empty(slot)) {
+            Some(slot) => {
+                self.slots[slot] = Some(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+
+    /// Like `add_item`, but fails with `ShrubError::NotAllowed` when no slot accepts the item.
+    fn try_add_item(&mut self, item: Item) -> Result<(), ShrubError> {
+        if self.accepting_slots(&item).next().is_none() {
+            return Err(ShrubError::NotAllowed(item));
+        }
+        match self.add_item(item) {
+            Some(item) => Err(ShrubError::InventoryFull(item)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl InventorySelector<usize> for SlotInventory {
+    fn get_item(&self, selector: usize) -> Option<&Item> {
+        self.slots.get(selector)?.as_ref()
+    }
+
+    fn get_item_mut(&mut self, selector: usize) -> Option<&mut Item> {
+        self.slots.get_mut(selector)?.as_mut()
+    }
+
+    fn remove_item(&mut self, selector: usize) -> Option<Item> {
+        self.slots.get_mut(selector)?.take()
+    }
+}
+
+impl InventoryItems for SlotInventory {
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.slots.iter().flatten()
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.slots.iter_mut().flatten()
+    }
+}
+
+impl InventoryEntries<usize> for SlotInventory {
+    const STABLE_SELECTORS: bool = true;
+
+    fn entries(&self) -> impl Iterator<Item = (usize, &Item)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.as_ref()?)))
+    }
+}
+
+impl TransactionInventory<usize> for SlotInventory {
+    fn add_item_tracked(&mut self, mut item: Item) -> Result<AddReceipt<usize>, ShrubError> {
+        // decided once for the whole item, as filters may depend on the count of the shrinking remainder
+        let accepting: Vec<usize> = self.accepting_slots(&item).collect();
+        if accepting.is_empty() {
+            return Err(ShrubError::NotAllowed(item));
+        }
+        let empty_slot = accepting
+            .iter()
+            .copied()
+            .find(|&slot| self.is_slot_empty(slot));
+        if empty_slot.is_none() {
+            let room: u32 = accepting
+                .iter()
+                .filter_map(|&slot| self.slots[slot].as_ref())
+                .filter(|stack| stack.can_stack_with(&item))
+                .map(|stack| stack.max_stack_size().saturating_sub(stack.count()))
+                .fold(0, u32::saturating_add);
+            if room < item.count() {
+                return Err(ShrubError::InventoryFull(item));
+            }
+        }
+
+        let mut merged = Vec::new();
+        for &slot in &accepting {
+            if item.count() == 0 {
+                break;
+            }
+            let Some(stack) = self.slots[slot].as_mut() else {
+                continue;
+            };
+            match stack.take_from(&mut item) {
+                0 => {}
+                count => merged.push((slot, count)),
+            }
+        }
+        if item.count() == 0 {
+            return Ok(AddReceipt::merged(merged, item));
+        }
+        match empty_slot {
+            Some(slot) => {
+                self.slots[slot] = Some(item);
+                Ok(AddReceipt::placed(merged, slot))
+            }
+            None => Err(ShrubError::InventoryFull(
+                AddReceipt::merged(merged, item).undo(self),
+            )),
+        }
+    }
+
+    fn insert_item(&mut self, selector: usize, item: Item) -> Option<Item> {
+        SlotInventory::insert_item(self, selector, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        Inventory, InventorySelector, ItemData, ItemFilter, ItemType, MaxStackSize, ShrubError,
+        SlotInventory, TransactionInventory,
+    };
+
+    struct NumberData(u32);
+    impl ItemData for NumberData {}
+
+    #[test]
+    fn create_empty_inventory() {
+        let inventory = SlotInventory::new(3);
+        assert_eq!(inventory.capacity(), 3);
+        assert_eq!(inventory.len(), 0);
+        assert!(inventory.is_empty());
+        assert!(!inventory.is_full());
+        assert_eq!(inventory.empty_slots().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn add_item_until_full() {
+        let item_type = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(2);
+
+        assert!(inventory.add_item(item_type.item_new()).is_none());
+        assert!(inventory.add_item(item_type.item_new()).is_none());
+        assert!(inventory.is_full());
+
+        let rejected = inventory.add_item(item_type.item_with_data(NumberData(3)));
+        assert_eq!(rejected.unwrap().get_data::<NumberData>().unwrap().0, 3);
+        assert_eq!(inventory.len(), 2);
+    }
+
+    #[test]
+    fn add_item_fills_gaps() {
+        let item_type = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(3);
+        inventory.insert_item(0, item_type.item_new());
+        inventory.insert_item(2, item_type.item_new());
+
+        assert_eq!(inventory.first_empty_slot(), Some(1));
+        assert!(inventory.add_item(item_type.item_new()).is_none());
+        assert!(!inventory.is_slot_empty(1));
+        assert!(inventory.is_full());
+    }
+
+    #[test]
+    fn remove_item_frees_slot() {
+        let item_type = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(2);
+        inventory.add_item(item_type.item_with_data(NumberData(7)));
+
+        let item = inventory.remove_item(0).unwrap();
+        assert_eq!(item.get_data::<NumberData>().unwrap().0, 7);
+        assert!(inventory.is_slot_empty(0));
+        assert!(inventory.remove_item(0).is_none());
+        assert!(inventory.remove_item(5).is_none());
+    }
+
+    #[test]
+    fn move_and_swap_items() {
+        let item_type = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(3);
+        inventory.insert_item(0, item_type.item_with_data(NumberData(1)));
+        inventory.insert_item(1, item_type.item_with_data(NumberData(2)));
+
+        assert!(!inventory.move_item(0, 1));
+        assert!(inventory.move_item(0, 2));
+        assert!(inventory.is_slot_empty(0));
+
+        assert!(inventory.swap_slots(1, 2));
+        let numbers: Vec<(usize, u32)> = inventory
+            .iter()
+            .map(|(slot, item)| (slot, item.get_data::<NumberData>().unwrap().0))
+            .collect();
+        assert_eq!(numbers, vec![(1, 1), (2, 2)]);
+        assert!(!inventory.swap_slots(0, 3));
+    }
+
+    #[test]
+    fn modify_items_in_place() {
+        let item_type = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(2);
+        inventory.add_item(item_type.item_with_data(NumberData(1)));
+        inventory.add_item(item_type.item_with_data(NumberData(2)));
+
+        for (_, item) in inventory.iter_mut() {
+            item.get_data_mut::<NumberData>().unwrap().0 *= 10;
+        }
+        inventory
+            .get_item_mut(1)
+            .unwrap()
+            .get_data_mut::<NumberData>()
+            .unwrap()
+            .0 += 1;
+
+        assert_eq!(
+            inventory
+                .get_item(0)
+                .unwrap()
+                .get_data::<NumberData>()
+                .unwrap()
+                .0,
+            10
+        );
+        assert_eq!(
+            inventory
+                .get_item(1)
+                .unwrap()
+                .get_data::<NumberData>()
+                .unwrap()
+                .0,
+            21
+        );
+    }
+
+    #[test]
+    fn report_why_items_are_rejected() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(8)));
+        let mut inventory = SlotInventory::new(1);
+        let mut arrows = arrow.item_new();
+        arrows.set_count(5);
+        inventory.try_insert_item(0, arrows).unwrap();
+
+        let error = inventory.try_insert_item(0, arrow.item_new()).unwrap_err();
+        assert!(matches!(error, ShrubError::SlotOccupied(_)));
+        let error = inventory.try_insert_item(1, arrow.item_new()).unwrap_err();
+        assert!(matches!(error, ShrubError::InvalidSlot(_)));
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(6);
+        match inventory.try_add_item(arrows) {
+            Err(ShrubError::InventoryFull(overflow)) => assert_eq!(overflow.count(), 3),
+            _ => panic!("the inventory should be full"),
+        }
+        assert_eq!(inventory.try_remove_item(0).unwrap().count(), 8);
+        assert!(matches!(
+            inventory.try_remove_item(0),
+            Err(ShrubError::SlotEmpty)
+        ));
+    }
+
+    #[test]
+    fn add_item_merges_stacks() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+        let mut inventory = SlotInventory::new(2);
+        let mut arrows = arrow.item_new();
+        arrows.set_count(40);
+        inventory.add_item(arrows);
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(40);
+        assert!(inventory.add_item(arrows).is_none());
+
+        assert_eq!(inventory.get_item(0).unwrap().count(), 64);
+        assert_eq!(inventory.get_item(1).unwrap().count(), 16);
+    }
+
+    #[test]
+    fn add_item_returns_overflow() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+        let sword = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(2);
+        inventory.add_item(sword.item_new());
+        let mut arrows = arrow.item_new();
+        arrows.set_count(60);
+        inventory.add_item(arrows);
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(10);
+        let overflow = inventory.add_item(arrows).unwrap();
+        assert_eq!(overflow.count(), 6);
+        assert_eq!(inventory.get_item(1).unwrap().count(), 64);
+
+        assert!(inventory.add_item(sword.item_new()).is_some());
+    }
+
+    #[test]
+    fn add_item_keeps_items_with_data_separate() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+        let mut inventory = SlotInventory::new(2);
+        inventory.add_item(arrow.item_new());
+        inventory.add_item(arrow.item_with_data(NumberData(1)));
+
+        assert_eq!(inventory.len(), 2);
+        assert_eq!(inventory.get_item(0).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn filter_items() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let sword = Arc::new(ItemType::new());
+        let mut quiver = SlotInventory::new(2).with_filter(ItemFilter::item_types([arrow.clone()]));
+        quiver.set_slot_filter(1, Some(ItemFilter::new(|item| item.count() >= 10)));
+
+        assert!(!quiver.accepts(&sword.item_new()));
+        assert!(quiver.accepting_slots(&sword.item_new()).next().is_none());
+        assert!(matches!(
+            quiver.try_add_item(sword.item_new()),
+            Err(ShrubError::NotAllowed(_))
+        ));
+        assert!(matches!(
+            quiver.add_item_tracked(sword.item_new()),
+            Err(ShrubError::NotAllowed(_))
+        ));
+        assert!(matches!(
+            quiver.try_insert_item(1, arrow.item_new()),
+            Err(ShrubError::NotAllowed(_))
+        ));
+
+        // the second slot only takes big stacks, so the single arrows all go into the first
+        assert_eq!(
+            quiver
+                .accepting_slots(&arrow.item_new())
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert!(quiver.add_item(arrow.item_new()).is_none());
+        assert!(quiver.add_item(arrow.item_new()).is_none());
+        assert_eq!(quiver.get_item(0).unwrap().count(), 2);
+        assert!(quiver.is_slot_empty(1));
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(30);
+        assert!(quiver.add_item(arrows).is_none());
+        assert_eq!(quiver.get_item(0).unwrap().count(), 16);
+        assert_eq!(quiver.get_item(1).unwrap().count(), 16);
+
+        quiver.set_slot_filter(1, None);
+        assert!(quiver.slot_accepts(1, &arrow.item_new()));
+        assert!(quiver.slot_filter(1).is_none());
+    }
+
+    #[test]
+    fn add_tracked_items_to_slots_filtered_by_count() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut quiver = SlotInventory::new(2);
+        quiver.set_slot_filter(1, Some(ItemFilter::new(|item| item.count() >= 10)));
+        for slot in 0..2 {
+            let mut arrows = arrow.item_new();
+            arrows.set_count(10);
+            assert!(quiver.insert_item(slot, arrows).is_none());
+        }
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(12);
+        assert!(quiver.add_item_tracked(arrows).is_ok());
+        assert_eq!(quiver.get_item(0).unwrap().count(), 16);
+        assert_eq!(quiver.get_item(1).unwrap().count(), 16);
+    }
+
+    #[test]
+    fn add_items_to_slots_filtered_by_count() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut quiver = SlotInventory::new(3);
+        quiver.set_slot_filter(1, Some(ItemFilter::new(|item| item.count() >= 10)));
+        for slot in 0..2 {
+            let mut arrows = arrow.item_new();
+            arrows.set_count(10);
+            assert!(quiver.insert_item(slot, arrows).is_none());
+        }
+
+        let mut arrows = arrow.item_new();
+        arrows.set_count(14);
+        assert!(quiver.add_item(arrows).is_none());
+        assert_eq!(quiver.get_item(0).unwrap().count(), 16);
+        assert_eq!(quiver.get_item(1).unwrap().count(), 16);
+        assert_eq!(quiver.get_item(2).unwrap().count(), 2);
+
+        // a single arrow isn't accepted by the second slot, even though it would fit there
+        quiver.remove_item(0);
+        quiver.remove_item(2);
+        quiver.get_item_mut(1).unwrap().set_count(12);
+        assert!(quiver.add_item(arrow.item_new()).is_none());
+        assert_eq!(quiver.get_item(0).unwrap().count(), 1);
+        assert_eq!(quiver.get_item(1).unwrap().count(), 12);
+    }
+
+    #[test]
+    fn keep_filtered_slots_on_move_and_swap() {
+        let arrow = Arc::new(ItemType::new());
+        let sword = Arc::new(ItemType::new());
+        let mut inventory = SlotInventory::new(3);
+        inventory.set_slot_filter(1, Some(ItemFilter::item_types([arrow.clone()])));
+        inventory.insert_item(0, sword.item_new());
+
+        assert!(!inventory.move_item(0, 1));
+        assert!(inventory.is_slot_empty(1));
+        assert!(!inventory.swap_slots(0, 1));
+        assert!(inventory.get_item(0).is_some());
+
+        inventory.insert_item(2, arrow.item_new());
+        assert!(inventory.move_item(2, 1));
+        assert!(!inventory.swap_slots(1, 0));
+        assert!(inventory.swap_slots(1, 2));
+        assert!(!inventory.swap_slots(0, 1));
+        assert!(inventory.swap_slots(0, 2));
+        assert_eq!(inventory.get_item(0).unwrap().item_type(), &arrow);
+    }
+}
@@ -0,0 +1,16 @@
+use crate::itemdata::impl_value_item_data;
+
+/// Limits how many items a single stack can hold, see `Item::merge`.
+/// Usually added to an `ItemType`, but single items can override it with their own data.
+///
+/// # Examples
+/// ```
+/// use shrub::{ItemType, MaxStackSize};
+///
+/// let arrow = ItemType::with_data(MaxStackSize(64));
+/// assert_eq!(arrow.max_stack_size(), 64);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxStackSize(pub u32);
+impl_value_item_data!(MaxStackSize);
@@ -0,0 +1,546 @@
+use std::{any::Any, fmt, marker::PhantomData};
+
+use crate::{InventorySelector, Item, ShrubError, ShrubErrorKind};
+
+/// Inventories that can take part in a `Transaction`.
+/// To be able to roll back, a transaction needs to know where added items ended up and put removed items back in place.
+pub trait TransactionInventory<S>: InventorySelector<S> {
+    /// Adds the whole item like `Inventory::add_item` and records where its parts ended up.
+    /// When the item doesn't fit completely, the inventory is left unchanged and the item is handed back in the error.
+    fn add_item_tracked(&mut self, item: Item) -> Result<AddReceipt<S>, ShrubError>;
+
+    /// Puts the item at the given selector.
+    /// * when the selector doesn't point at a free place, it will return the item in `Some(Item)`
+    fn insert_item(&mut self, selector: S, item: Item) -> Option<Item>;
+}
+
+/// Records where the parts of an item added by `TransactionInventory::add_item_tracked` ended up, so the add can be undone.
+pub struct AddReceipt<S> {
+    merged: Vec<(S, u32)>,
+    placed: Option<S>,
+    shell: Option<Item>,
+}
+
+impl<S> AddReceipt<S> {
+    /// The item was merged into the stacks at the selectors by the given amounts, and its remainder was put at `selector`.
+    pub fn placed(merged: Vec<(S, u32)>, selector: S) -> Self {
+        AddReceipt {
+            merged,
+            placed: Some(selector),
+            shell: None,
+        }
+    }
+
+    /// The item was merged completely into the stacks at the selectors by the given amounts.
+    /// `shell` is the item that was merged, which is left with a count of 0.
+    pub fn merged(merged: Vec<(S, u32)>, shell: Item) -> Self {
+        AddReceipt {
+            merged,
+            placed: None,
+            shell: Some(shell),
+        }
+    }
+
+    /// Iterates over the selectors of the stacks the item was merged into, followed by the one its remainder was put at.
+    pub(crate) fn selectors(&self) -> impl Iterator<Item = &S> {
+        self.merged
+            .iter()
+            .map(|(selector, _)| selector)
+            .chain(&self.placed)
+    }
+
+    /// Converts the selectors, for inventories that wrap other inventories.
+    pub fn map<T>(self, mut f: impl FnMut(S) -> T) -> AddReceipt<T> {
+        AddReceipt {
+            merged: self
+                .merged
+                .into_iter()
+                .map(|(selector, count)| (f(selector), count))
+                .collect(),
+            placed: self.placed.map(f),
+            shell: self.shell,
+        }
+    }
+
+    /// Takes the added item back out of the inventory.
+    ///
+    /// # Panics
+    /// Panics when the inventory was changed since the item was added, so it can't be found anymore.
+    pub fn undo<I: InventorySelector<S> + ?Sized>(self, inventory: &mut I) -> Item {
+        let mut item = match (self.placed, self.shell) {
+            (Some(selector), _) => inventory.remove_item(selector),
+            (None, shell) => shell,
+        }
+        .expect("the added item isn't in the inventory anymore");
+        for (selector, count) in self.merged {
+            let stack = inventory
+                .get_item_mut(selector)
+                .expect("the stack the item was merged into isn't in the inventory anymore");
+            stack.set_count(stack.count() - count);
+            item.set_count(item.count() + count);
+        }
+        item
+    }
+}
+
+/// Refers to an inventory that takes part in a `Transaction`, whose items are selected with `S`.
+pub struct InventoryHandle<S> {
+    index: usize,
+    _selector: PhantomData<fn(S)>,
+}
+
+impl<S> Clone for InventoryHandle<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for InventoryHandle<S> {}
+
+/// Stages adds, removes and moves across several inventories and applies them all at once.
+///
+/// Nothing happens until `commit`. When any operation fails there, all previous operations are rolled back,
+/// so every item is back in its original place and the items that should've been added are handed back.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{Inventory, InventorySelector, ItemType, SlotInventory, Transaction};
+///
+/// let wood = Arc::new(ItemType::new());
+/// let table = Arc::new(ItemType::new());
+/// let mut workbench = SlotInventory::new(2);
+/// workbench.insert_item(0, wood.item_new());
+/// let mut backpack = SlotInventory::new(1);
+/// backpack.add_item(wood.item_new());
+///
+/// // crafting a table takes the wood out of the workbench and puts the table into the full backpack
+/// let mut transaction = Transaction::new();
+/// let input = transaction.inventory(&mut workbench);
+/// let output = transaction.inventory(&mut backpack);
+/// transaction.remove_item(input, 0).add_item(output, table.item_new());
+/// let error = transaction.commit().unwrap_err();
+///
+/// assert_eq!(error.operation(), 1);
+/// let table = error.into_items().pop().unwrap();
+/// assert!(workbench.get_item(0).is_some()); // the wood is back in its place
+/// ```
+pub struct Transaction<'i> {
+    inventories: Vec<Box<dyn ErasedInventory + 'i>>,
+    operations: Vec<Operation>,
+}
+
+enum Operation {
+    Add {
+        inventory: usize,
+        item: Item,
+    },
+    Remove {
+        inventory: usize,
+        selector: Box<dyn Any>,
+    },
+    Move {
+        from: usize,
+        selector: Box<dyn Any>,
+        to: usize,
+    },
+}
+
+/// An applied operation, as far as it is needed to undo it.
+enum Applied {
+    Added {
+        inventory: usize,
+        receipt: Box<dyn Any>,
+        /// The index of the add operation, or `None` when the item was moved from another inventory.
+        operation: Option<usize>,
+    },
+    Removed {
+        inventory: usize,
+        selector: Box<dyn Any>,
+        /// Whether the item was handed out by `commit` or moved into another inventory.
+        handed_out: bool,
+    },
+}
+
+impl Default for Transaction<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'i> Transaction<'i> {
+    /// Creates a new Transaction without any inventories or operations.
+    pub fn new() -> Self {
+        Transaction {
+            inventories: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Lets the inventory take part in the transaction and returns the handle to stage operations on it with.
+    pub fn inventory<S, I>(&mut self, inventory: &'i mut I) -> InventoryHandle<S>
+    where
+        S: Clone + 'static,
+        I: TransactionInventory<S> + ?Sized,
+    {
+        self.inventories
+            .push(Box::new(Erased(inventory, PhantomData::<fn(S)>)));
+        InventoryHandle {
+            index: self.inventories.len() - 1,
+            _selector: PhantomData,
+        }
+    }
+
+    /// Stages adding the whole item to the inventory.
+    pub fn add_item<S>(&mut self, inventory: InventoryHandle<S>, item: Item) -> &mut Self {
+        self.operations.push(Operation::Add {
+            inventory: inventory.index,
+            item,
+        });
+        self
+    }
+
+    /// Stages removing the item at the selector. Removed items are returned by `commit`.
+    pub fn remove_item<S: 'static>(
+        &mut self,
+        inventory: InventoryHandle<S>,
+        selector: S,
+    ) -> &mut Self {
+        self.operations.push(Operation::Remove {
+            inventory: inventory.index,
+            selector: Box::new(selector),
+        });
+        self
+    }
+
+    /// Stages moving the whole item at the selector into another inventory.
+    pub fn move_item<S: 'static, T>(
+        &mut self,
+        from: InventoryHandle<S>,
+        selector: S,
+        to: InventoryHandle<T>,
+    ) -> &mut Self {
+        self.operations.push(Operation::Move {
+            from: from.index,
+            selector: Box::new(selector),
+            to: to.index,
+        });
+        self
+    }
+
+    /// Applies all staged operations in order and returns the removed items in the order their removes were staged.
+    ///
+    /// # Panics
+    /// Panics when an operation was staged with a handle of another transaction.
+    pub fn commit(mut self) -> Result<Vec<Item>, TransactionError> {
+        let mut applied = Vec::with_capacity(self.operations.len());
+        let mut removed = Vec::new();
+        let mut operations = std::mem::take(&mut self.operations).into_iter().enumerate();
+
+        let failure = loop {
+            let Some((index, operation)) = operations.next() else {
+                return Ok(removed);
+            };
+            match operation {
+                Operation::Add { inventory, item } => match self.inventories[inventory].add(item) {
+                    Ok(receipt) => applied.push(Applied::Added {
+                        inventory,
+                        receipt,
+                        operation: Some(index),
+                    }),
+                    Err(error) => break (index, error.kind(), error.into_item()),
+                },
+                Operation::Remove {
+                    inventory,
+                    selector,
+                } => match self.inventories[inventory].remove(selector) {
+                    Ok((item, selector)) => {
+                        removed.push(item);
+                        applied.push(Applied::Removed {
+                            inventory,
+                            selector,
+                            handed_out: true,
+                        });
+                    }
+                    Err(error) => break (index, error.kind(), None),
+                },
+                Operation::Move { from, selector, to } => {
+                    let (item, selector) = match self.inventories[from].remove(selector) {
+                        Ok(removed) => removed,
+                        Err(error) => break (index, error.kind(), None),
+                    };
+                    match self.inventories[to].add(item) {
+                        Ok(receipt) => {
+                            applied.push(Applied::Removed {
+                                inventory: from,
+                                selector,
+                                handed_out: false,
+                            });
+                            applied.push(Applied::Added {
+                                inventory: to,
+                                receipt,
+                                operation: None,
+                            });
+                        }
+                        Err(error) => {
+                            let kind = error.kind();
+                            let item = error.into_item().expect("failed adds hand back the item");
+                            self.inventories[from].undo_remove(selector, item);
+                            break (index, kind, None);
+                        }
+                    }
+                }
+            }
+        };
+
+        let (operation, kind, failed_item) = failure;
+        let mut returned: Vec<(usize, Item)> = failed_item
+            .map(|item| (operation, item))
+            .into_iter()
+            .collect();
+        let mut moving = Vec::new();
+        for applied in applied.into_iter().rev() {
+            match applied {
+                Applied::Added {
+                    inventory,
+                    receipt,
+                    operation,
+                } => {
+                    let item = self.inventories[inventory].undo_add(receipt);
+                    match operation {
+                        Some(operation) => returned.push((operation, item)),
+                        None => moving.push(item),
+                    }
+                }
+                Applied::Removed {
+                    inventory,
+                    selector,
+                    handed_out,
+                } => {
+                    let item = if handed_out {
+                        removed.pop()
+                    } else {
+                        moving.pop()
+                    };
+                    self.inventories[inventory].undo_remove(selector, item.unwrap());
+                }
+            }
+        }
+        returned.extend(operations.filter_map(|(index, operation)| match operation {
+            Operation::Add { item, .. } => Some((index, item)),
+            _ => None,
+        }));
+        returned.sort_by_key(|(index, _)| *index);
+
+        Err(TransactionError {
+            operation,
+            kind,
+            items: returned.into_iter().map(|(_, item)| item).collect(),
+        })
+    }
+}
+
+/// Why `Transaction::commit` failed. Every inventory is back in the state before the commit.
+pub struct TransactionError {
+    operation: usize,
+    kind: ShrubErrorKind,
+    items: Vec<Item>,
+}
+
+impl TransactionError {
+    /// Returns the index of the staged operation that failed.
+    #[inline]
+    pub fn operation(&self) -> usize {
+        self.operation
+    }
+
+    /// Returns why the operation failed.
+    #[inline]
+    pub fn kind(&self) -> ShrubErrorKind {
+        self.kind
+    }
+
+    /// Borrows the items of all staged adds, in the order they were staged.
+    #[inline]
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Takes the items of all staged adds, in the order they were staged.
+    #[inline]
+    pub fn into_items(self) -> Vec<Item> {
+        self.items
+    }
+}
+
+impl fmt::Debug for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionError")
+            .field("operation", &self.operation)
+            .field("kind", &self.kind)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "operation {} of the transaction failed: {:?}",
+            self.operation, self.kind
+        )
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// A `TransactionInventory` with its selector type erased, so inventories with different selectors can be mixed.
+trait ErasedInventory {
+    fn add(&mut self, item: Item) -> Result<Box<dyn Any>, ShrubError>;
+    fn undo_add(&mut self, receipt: Box<dyn Any>) -> Item;
+    fn remove(&mut self, selector: Box<dyn Any>) -> Result<(Item, Box<dyn Any>), ShrubError>;
+    fn undo_remove(&mut self, selector: Box<dyn Any>, item: Item);
+}
+
+struct Erased<'i, I: ?Sized, S>(&'i mut I, PhantomData<fn(S)>);
+
+impl<I: TransactionInventory<S> + ?Sized, S: Clone + 'static> ErasedInventory for Erased<'_, I, S> {
+    fn add(&mut self, item: Item) -> Result<Box<dyn Any>, ShrubError> {
+        let receipt = self.0.add_item_tracked(item)?;
+        Ok(Box::new(receipt))
+    }
+
+    fn undo_add(&mut self, receipt: Box<dyn Any>) -> Item {
+        receipt.downcast::<AddReceipt<S>>().unwrap().undo(self.0)
+    }
+
+    fn remove(&mut self, selector: Box<dyn Any>) -> Result<(Item, Box<dyn Any>), ShrubError> {
+        let selector = selector
+            .downcast::<S>()
+            .expect("the inventory handle belongs to another transaction");
+        let item = self.0.try_remove_item(S::clone(&selector))?;
+        Ok((item, selector))
+    }
+
+    fn undo_remove(&mut self, selector: Box<dyn Any>, item: Item) {
+        let selector = *selector.downcast::<S>().unwrap();
+        assert!(
+            self.0.insert_item(selector, item).is_none(),
+            "the inventory doesn't take back a removed item"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        Inventory, InventorySelector, ItemData, ItemType, MaxStackSize, ShrubErrorKind,
+        SlotInventory, Transaction,
+    };
+
+    struct Name(&'static str);
+    impl ItemData for Name {}
+
+    fn names(inventory: &SlotInventory) -> Vec<Option<(&'static str, u32)>> {
+        (0..inventory.capacity())
+            .map(|slot| {
+                let item = inventory.get_item(slot)?;
+                Some((item.get_data::<Name>().unwrap().0, item.count()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commit_across_inventories() {
+        let item_type = Arc::new(ItemType::new());
+        let mut chest = SlotInventory::new(2);
+        chest.add_item(item_type.item_with_data(Name("sword")));
+        chest.add_item(item_type.item_with_data(Name("shield")));
+        let mut player = SlotInventory::new(2);
+
+        let mut transaction = Transaction::new();
+        let from = transaction.inventory(&mut chest);
+        let to = transaction.inventory(&mut player);
+        transaction
+            .move_item(from, 1, to)
+            .remove_item(from, 0)
+            .add_item(from, item_type.item_with_data(Name("gold")));
+        let removed = transaction.commit().unwrap();
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].get_data::<Name>().unwrap().0, "sword");
+        assert_eq!(names(&chest), vec![Some(("gold", 1)), None]);
+        assert_eq!(names(&player), vec![Some(("shield", 1)), None]);
+    }
+
+    #[test]
+    fn rollback_restores_everything() {
+        let arrow = Arc::new(ItemType::with_data((MaxStackSize(16), Name("arrow"))));
+        let stone = Arc::new(ItemType::with_data(Name("stone")));
+        let mut chest = SlotInventory::new(3);
+        let mut arrows = arrow.item_new();
+        arrows.set_count(10);
+        chest.insert_item(0, arrows);
+        chest.insert_item(2, stone.item_new());
+        let mut player = SlotInventory::new(2);
+        let mut arrows = arrow.item_new();
+        arrows.set_count(12);
+        player.insert_item(1, arrows);
+        let chest_before = names(&chest);
+        let player_before = names(&player);
+
+        let mut transaction = Transaction::new();
+        let chest_handle = transaction.inventory(&mut chest);
+        let player_handle = transaction.inventory(&mut player);
+        let mut more_arrows = arrow.item_new();
+        more_arrows.set_count(4);
+        transaction
+            .add_item(player_handle, more_arrows) // merged completely
+            .move_item(player_handle, 1, chest_handle) // partially merged, then placed
+            .remove_item(chest_handle, 2)
+            .add_item(chest_handle, stone.item_new())
+            .add_item(player_handle, stone.item_new())
+            .add_item(player_handle, stone.item_new())
+            .add_item(chest_handle, stone.item_new()) // doesn't fit anymore
+            .add_item(player_handle, stone.item_new());
+        let error = transaction.commit().unwrap_err();
+
+        assert_eq!(error.operation(), 6);
+        assert_eq!(error.kind(), ShrubErrorKind::InventoryFull);
+        let returned: Vec<u32> = error.items().iter().map(|item| item.count()).collect();
+        assert_eq!(returned, vec![4, 1, 1, 1, 1, 1]);
+        assert_eq!(names(&chest), chest_before);
+        assert_eq!(names(&player), player_before);
+    }
+
+    #[test]
+    fn rollback_failed_move_and_remove() {
+        let item_type = Arc::new(ItemType::new());
+        let mut chest = SlotInventory::new(1);
+        chest.add_item(item_type.item_with_data(Name("sword")));
+        let mut full = SlotInventory::new(1);
+        full.add_item(item_type.item_with_data(Name("shield")));
+
+        let mut transaction = Transaction::new();
+        let from = transaction.inventory(&mut chest);
+        let to = transaction.inventory(&mut full);
+        transaction.move_item(from, 0, to);
+        let error = transaction.commit().unwrap_err();
+        assert_eq!(error.kind(), ShrubErrorKind::InventoryFull);
+        assert!(error.items().is_empty());
+        assert_eq!(names(&chest), vec![Some(("sword", 1))]);
+
+        let mut transaction = Transaction::new();
+        let from = transaction.inventory(&mut chest);
+        transaction.remove_item(from, 0).remove_item(from, 0);
+        let error = transaction.commit().unwrap_err();
+        assert_eq!(
+            (error.operation(), error.kind()),
+            (1, ShrubErrorKind::SlotEmpty)
+        );
+        assert_eq!(names(&chest), vec![Some(("sword", 1))]);
+    }
+}
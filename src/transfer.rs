@@ -0,0 +1,190 @@
+use crate::{Inventory, ShrubError, TransactionInventory};
+
+/// Moves the whole stack at the selector from one inventory into another.
+/// Whatever doesn't fit into `to` stays at its place in `from`. Returns how many items were moved.
+///
+/// Fails with `ShrubError::SlotEmpty` when there is no item at the selector.
+/// Only when `from` doesn't take the remainder back at its old place, it is handed back in `ShrubError::InventoryFull`.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{transfer, Inventory, InventorySelector, ItemType, MaxStackSize, SlotInventory};
+///
+/// let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+/// let mut chest = SlotInventory::new(1);
+/// let mut arrows = arrow.item_new();
+/// arrows.set_count(50);
+/// chest.add_item(arrows);
+///
+/// let mut quiver = SlotInventory::new(1);
+/// let mut arrows = arrow.item_new();
+/// arrows.set_count(40);
+/// quiver.add_item(arrows);
+///
+/// assert_eq!(transfer(&mut chest, 0, &mut quiver).unwrap(), 24);
+/// assert_eq!(chest.get_item(0).unwrap().count(), 26);
+/// assert_eq!(quiver.get_item(0).unwrap().count(), 64);
+/// ```
+pub fn transfer<S, F, T>(from: &mut F, selector: S, to: &mut T) -> Result<u32, ShrubError>
+where
+    S: Clone,
+    F: TransactionInventory<S> + ?Sized,
+    T: Inventory + ?Sized,
+{
+    let item = from.try_remove_item(selector.clone())?;
+    let count = item.count();
+    match to.add_item(item) {
+        Some(remainder) => {
+            let moved = count - remainder.count();
+            match from.insert_item(selector, remainder) {
+                Some(remainder) => Err(ShrubError::InventoryFull(remainder)),
+                None => Ok(moved),
+            }
+        }
+        None => Ok(count),
+    }
+}
+
+/// Moves up to `amount` items of the stack at the selector from one inventory into another.
+/// Whatever doesn't fit into `to` stays in the stack in `from`. Returns how many items were moved.
+///
+/// Fails with `ShrubError::SlotEmpty` when there is no item at the selector,
+/// and with `ShrubError::CannotSplit` when only a part of the stack should be moved but it can't be split, see `Item::split`.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use shrub::{transfer_amount, Inventory, InventorySelector, ItemType, MaxStackSize, SlotInventory};
+///
+/// let arrow = Arc::new(ItemType::with_data(MaxStackSize(64)));
+/// let mut chest = SlotInventory::new(1);
+/// let mut arrows = arrow.item_new();
+/// arrows.set_count(50);
+/// chest.add_item(arrows);
+/// let mut quiver = SlotInventory::new(1);
+///
+/// assert_eq!(transfer_amount(&mut chest, 0, &mut quiver, 20).unwrap(), 20);
+/// assert_eq!(chest.get_item(0).unwrap().count(), 30);
+/// assert_eq!(quiver.get_item(0).unwrap().count(), 20);
+/// ```
+pub fn transfer_amount<S, F, T>(
+    from: &mut F,
+    selector: S,
+    to: &mut T,
+    amount: u32,
+) -> Result<u32, ShrubError>
+where
+    S: Clone,
+    F: TransactionInventory<S> + ?Sized,
+    T: Inventory + ?Sized,
+{
+    let stack = from
+        .get_item_mut(selector.clone())
+        .ok_or(ShrubError::SlotEmpty)?;
+    if amount >= stack.count() {
+        return transfer(from, selector, to);
+    }
+    if amount == 0 {
+        return Ok(0);
+    }
+    let part = stack.split(amount).ok_or(ShrubError::CannotSplit)?;
+    match to.add_item(part) {
+        Some(remainder) => {
+            // the remainder is a part of the same stack, so it goes back without looking at the max stack size
+            let stack = from.get_item_mut(selector).unwrap();
+            stack.set_count(stack.count() + remainder.count());
+            Ok(amount - remainder.count)
+        }
+        None => Ok(amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        transfer, transfer_amount, Inventory, InventorySelector, ItemData, ItemType, MaxStackSize,
+        ShrubError, SlotInventory,
+    };
+
+    struct Name(&'static str);
+    impl ItemData for Name {}
+
+    fn stack(item_type: &Arc<ItemType>, count: u32) -> crate::Item {
+        let mut item = item_type.item_new();
+        item.set_count(count);
+        item
+    }
+
+    #[test]
+    fn transfer_whole_item() {
+        let sword = Arc::new(ItemType::new());
+        let mut chest = SlotInventory::new(2);
+        chest.insert_item(1, sword.item_with_data(Name("excalibur")));
+        let mut player = SlotInventory::new(1);
+
+        assert_eq!(transfer(&mut chest, 1, &mut player).unwrap(), 1);
+        assert!(chest.is_empty());
+        assert_eq!(
+            player.get_item(0).unwrap().get_data::<Name>().unwrap().0,
+            "excalibur"
+        );
+
+        assert!(matches!(
+            transfer(&mut chest, 1, &mut player),
+            Err(ShrubError::SlotEmpty)
+        ));
+    }
+
+    #[test]
+    fn transfer_keeps_what_doesnt_fit() {
+        let sword = Arc::new(ItemType::new());
+        let mut chest = SlotInventory::new(3);
+        chest.insert_item(2, sword.item_with_data(Name("excalibur")));
+        let mut full = SlotInventory::new(1);
+        full.add_item(sword.item_new());
+
+        assert_eq!(transfer(&mut chest, 2, &mut full).unwrap(), 0);
+        assert_eq!(
+            chest.get_item(2).unwrap().get_data::<Name>().unwrap().0,
+            "excalibur"
+        );
+    }
+
+    #[test]
+    fn transfer_partial_stacks() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut chest = SlotInventory::new(1);
+        chest.add_item(stack(&arrow, 20));
+        let mut quiver = SlotInventory::new(1);
+        quiver.add_item(stack(&arrow, 10));
+
+        assert_eq!(transfer_amount(&mut chest, 0, &mut quiver, 8).unwrap(), 6);
+        assert_eq!(chest.get_item(0).unwrap().count(), 14);
+        assert_eq!(quiver.get_item(0).unwrap().count(), 16);
+
+        assert_eq!(transfer_amount(&mut chest, 0, &mut quiver, 0).unwrap(), 0);
+        assert_eq!(transfer_amount(&mut chest, 0, &mut quiver, 20).unwrap(), 0);
+        assert_eq!(chest.get_item(0).unwrap().count(), 14);
+    }
+
+    #[test]
+    fn transfer_amount_of_items_with_data() {
+        let arrow = Arc::new(ItemType::with_data(MaxStackSize(16)));
+        let mut chest = SlotInventory::new(1);
+        let mut arrows = arrow.item_with_data(Name("fire arrow"));
+        arrows.set_count(4);
+        chest.add_item(arrows);
+        let mut quiver = SlotInventory::new(1);
+
+        assert!(matches!(
+            transfer_amount(&mut chest, 0, &mut quiver, 2),
+            Err(ShrubError::CannotSplit)
+        ));
+        assert_eq!(chest.get_item(0).unwrap().count(), 4);
+        assert_eq!(transfer_amount(&mut chest, 0, &mut quiver, 4).unwrap(), 4);
+        assert!(chest.is_empty());
+    }
+}